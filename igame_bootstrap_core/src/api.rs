@@ -0,0 +1,447 @@
+use crate::channel::UpdateChannel;
+use crate::error::process_error;
+use crate::time::{unix_timestamp, utc_str_to_china_str};
+use crate::workspace::get_workspace_root_path;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::static_var;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderGroup {
+    Fast,
+    #[allow(dead_code)]
+    Normal,
+}
+
+impl fmt::Display for ProviderGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderGroup::Fast => write!(f, "fast"),
+            ProviderGroup::Normal => write!(f, "normal"),
+        }
+    }
+}
+
+fn hand_api_response(e: ureq::Error) {
+    #[derive(Deserialize)]
+    struct ErrorResp {
+        code: u32,
+        content: String,
+    }
+
+    match e {
+        ureq::Error::Status(c, r) => match c {
+            400 => {
+                let error_response_json: ErrorResp = r
+                    .into_json()
+                    .map_err(|e| {
+                        process_error(
+                            format!("反序列化错误响应失败\n{:?}", e),
+                            true,
+                            true,
+                            true,
+                            true,
+                        );
+                    })
+                    .unwrap();
+                process_error(
+                    format!(
+                        "客户端发送请求错误，状态码400，请稍后再试\n{}",
+                        error_response_json.content
+                    ),
+                    true,
+                    true,
+                    true,
+                    true,
+                );
+            }
+            500 => {
+                let error_response_json: ErrorResp = r
+                    .into_json()
+                    .map_err(|e| {
+                        process_error(
+                            format!("反序列化错误响应失败\n{:?}", e),
+                            true,
+                            true,
+                            true,
+                            true,
+                        );
+                    })
+                    .unwrap();
+                if error_response_json.code == 500 {
+                    process_error(
+                        format!(
+                            "服务器维护中\n预计将于{:?}恢复正常",
+                            utc_str_to_china_str(error_response_json.content.as_str())
+                        ),
+                        true,
+                        true,
+                        false,
+                        true,
+                    );
+                } else {
+                    process_error(
+                        format!(
+                            "服务器响应错误，状态码500，请稍后再试\n{}",
+                            error_response_json.content
+                        ),
+                        true,
+                        true,
+                        true,
+                        true,
+                    );
+                }
+            }
+            _ => {
+                process_error(
+                    format!("服务器响应错误，状态码，请稍后再试{}\n{:?}", c, r),
+                    true,
+                    true,
+                    true,
+                    true,
+                );
+            }
+        },
+        ureq::Error::Transport(t) => {
+            process_error(
+                format!("从服务器获取响应失败\n{:?}", t),
+                true,
+                true,
+                true,
+                true,
+            );
+        }
+    }
+}
+
+// 只返回错误信息，不弹窗、不上报也不退出，供--dry-run使用
+fn describe_api_error(e: ureq::Error) -> String {
+    #[derive(Deserialize)]
+    struct ErrorResp {
+        code: u32,
+        content: String,
+    }
+
+    match e {
+        ureq::Error::Status(c, r) => match r.into_json::<ErrorResp>() {
+            Ok(v) if c == 500 && v.code == 500 => {
+                return format!(
+                    "服务器维护中\n预计将于{:?}恢复正常",
+                    utc_str_to_china_str(v.content.as_str())
+                );
+            }
+            Ok(v) => return format!("服务器响应错误，状态码{}\n{}", c, v.content),
+            Err(_) => return format!("服务器响应错误，状态码{}", c),
+        },
+        ureq::Error::Transport(t) => return format!("从服务器获取响应失败\n{:?}", t),
+    }
+}
+
+/// 正式版通道的请求与原来一致，其他通道附加channel参数
+pub fn get_channel_download_url(
+    resource_id: i32,
+    provider_group: &ProviderGroup,
+    channel: &UpdateChannel,
+) -> String {
+    #[derive(Deserialize)]
+    struct DownloadUrlResp {
+        download_url: String,
+    }
+
+    let mut request_url = format!(
+        "https://api.igame.ml/resource/{}/download_url?provider_group={}",
+        resource_id, provider_group
+    );
+    if *channel != UpdateChannel::Stable {
+        request_url.push_str(&format!("&channel={}", channel));
+    }
+    let response = (*static_var::UREQ_AGENT)
+        .get(request_url.as_str())
+        .call()
+        .map_err(|e| hand_api_response(e))
+        .unwrap();
+    let response_json: DownloadUrlResp = response
+        .into_json()
+        .map_err(|e| {
+            process_error(
+                format!("反序列化响应失败：{}\n{:?}", request_url, e),
+                true,
+                true,
+                true,
+                true,
+            );
+        })
+        .unwrap();
+
+    return response_json.download_url;
+}
+
+pub fn try_get_download_url(
+    resource_id: i32,
+    provider_group: &ProviderGroup,
+) -> Result<String, String> {
+    #[derive(Deserialize)]
+    struct DownloadUrlResp {
+        download_url: String,
+    }
+
+    let request_url = format!(
+        "https://api.igame.ml/resource/{}/download_url?provider_group={}",
+        resource_id, provider_group
+    );
+    let response = (*static_var::UREQ_AGENT)
+        .get(request_url.as_str())
+        .call()
+        .map_err(describe_api_error)?;
+    let response_json: DownloadUrlResp = response
+        .into_json()
+        .map_err(|e| format!("反序列化响应失败：{}\n{:?}", request_url, e))?;
+
+    return Ok(response_json.download_url);
+}
+
+pub fn get_resourc_version(resource_id: i32) -> String {
+    #[derive(Deserialize)]
+    struct ResourceVersionResp {
+        version: String,
+    }
+
+    let request_url = format!("https://api.igame.ml/resource/{}/version", resource_id);
+    let response = (*static_var::UREQ_AGENT)
+        .get(request_url.as_str())
+        .call()
+        .map_err(|e| hand_api_response(e))
+        .unwrap();
+    let response_json: ResourceVersionResp = response
+        .into_json()
+        .map_err(|e| {
+            process_error(
+                format!("反序列化响应失败：{}\n{:?}", request_url, e),
+                true,
+                true,
+                true,
+                true,
+            );
+        })
+        .unwrap();
+
+    return response_json.version;
+}
+
+// 更新检查的结果缓存在磁盘上，有效期内不再请求
+const UPDATE_CHECK_CACHE_PREFIX: &str = "update_check_";
+const UPDATE_CHECK_TTL_SECONDS: u64 = 60 * 60;
+// 更新检查在显示界面之前进行，不能等待太久
+const UPDATE_CHECK_TIMEOUT_SECONDS: u64 = 5;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    #[serde(rename = "version")]
+    pub latest_version: String,
+    /// 低于这个版本时必须更新
+    #[serde(default)]
+    pub min_version: Option<String>,
+    #[serde(default)]
+    pub release_notes: String,
+    /// 分阶段推送，没有时推送给所有用户
+    #[serde(default)]
+    pub rollout_percentage: Option<u8>,
+    /// 这些分组不受推送比例限制
+    #[serde(default)]
+    pub rollout_cohorts: Vec<String>,
+    #[serde(default)]
+    pub rollout_paused: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct UpdateCheckCache {
+    request_url: String,
+    etag: Option<String>,
+    checked_at: u64,
+    update_info: UpdateInfo,
+}
+
+// 每个资源单独缓存
+fn update_check_cache_path(resource_id: i32) -> PathBuf {
+    let mut cache_path = get_workspace_root_path();
+    cache_path.push(format!("{}{}.json", UPDATE_CHECK_CACHE_PREFIX, resource_id));
+    return cache_path;
+}
+
+// 切换通道后请求地址不同，之前的缓存不再使用
+fn read_update_check_cache(resource_id: i32, request_url: &str) -> Option<UpdateCheckCache> {
+    let content = fs::read_to_string(update_check_cache_path(resource_id)).ok()?;
+    let cache: UpdateCheckCache = serde_json::from_str(&content).ok()?;
+    if cache.request_url != request_url {
+        return None;
+    }
+    return Some(cache);
+}
+
+fn write_update_check_cache(resource_id: i32, cache: &UpdateCheckCache) {
+    let _ = fs::create_dir_all(get_workspace_root_path());
+    if let Ok(v) = serde_json::to_string(cache) {
+        let _ = fs::write(update_check_cache_path(resource_id), v);
+    }
+}
+
+/// 请求失败时返回None，当作没有已知的更新，不影响后续启动
+pub fn get_update_info(resource_id: i32, channel: &UpdateChannel) -> Option<UpdateInfo> {
+    return check_update_info(resource_id, channel, false);
+}
+
+/// 不写缓存也不记录日志，供--dry-run使用
+pub fn peek_update_info(resource_id: i32, channel: &UpdateChannel) -> Option<UpdateInfo> {
+    return check_update_info(resource_id, channel, true);
+}
+
+fn check_update_info(
+    resource_id: i32,
+    channel: &UpdateChannel,
+    read_only: bool,
+) -> Option<UpdateInfo> {
+    let mut request_url = format!("https://api.igame.ml/resource/{}/version", resource_id);
+    if *channel != UpdateChannel::Stable {
+        request_url.push_str(&format!("?channel={}", channel));
+    }
+    let cache = read_update_check_cache(resource_id, &request_url);
+    let now = unix_timestamp();
+    if let Some(v) = &cache {
+        if now.saturating_sub(v.checked_at) < UPDATE_CHECK_TTL_SECONDS {
+            return Some(v.update_info.clone());
+        }
+    }
+
+    let mut request = (*static_var::UREQ_AGENT)
+        .get(request_url.as_str())
+        .timeout(Duration::from_secs(UPDATE_CHECK_TIMEOUT_SECONDS));
+    if let Some(etag) = cache.as_ref().and_then(|v| v.etag.as_ref()) {
+        request = request.set("If-None-Match", etag);
+    }
+    let response = match request.call() {
+        Ok(v) => v,
+        Err(e) => {
+            if !read_only {
+                process_error(
+                    format!("检查更新失败：{}\n{:?}", request_url, e),
+                    false,
+                    true,
+                    false,
+                    false,
+                );
+            }
+            return None;
+        }
+    };
+
+    // 内容没有变化，只刷新缓存时间
+    if response.status() == 304 {
+        let mut cache = cache?;
+        cache.checked_at = now;
+        if !read_only {
+            write_update_check_cache(resource_id, &cache);
+        }
+        return Some(cache.update_info);
+    }
+    let etag = response.header("ETag").map(|v| v.to_string());
+    let mut update_info: UpdateInfo = match response.into_json() {
+        Ok(v) => v,
+        Err(e) => {
+            if !read_only {
+                process_error(
+                    format!("反序列化响应失败：{}\n{:?}", request_url, e),
+                    false,
+                    true,
+                    false,
+                    false,
+                );
+            }
+            return None;
+        }
+    };
+    update_info.min_version = update_info.min_version.filter(|v| !v.is_empty());
+    if !read_only {
+        write_update_check_cache(
+            resource_id,
+            &UpdateCheckCache {
+                request_url,
+                etag,
+                checked_at: now,
+                update_info: update_info.clone(),
+            },
+        );
+    }
+
+    return Some(update_info);
+}
+
+pub struct UpdatePatch {
+    pub download_url: String,
+    pub sha256: String,
+}
+
+/// 查询从指定版本到最新版本的差分补丁，没有补丁或请求失败时返回None，由调用方改为完整更新
+pub fn get_update_patch(
+    resource_id: i32,
+    from_version: &str,
+    channel: &UpdateChannel,
+) -> Option<UpdatePatch> {
+    #[derive(Deserialize)]
+    struct UpdatePatchResp {
+        download_url: String,
+        sha256: String,
+    }
+
+    let mut request_url = format!(
+        "https://api.igame.ml/resource/{}/patch?from={}",
+        resource_id, from_version
+    );
+    if *channel != UpdateChannel::Stable {
+        request_url.push_str(&format!("&channel={}", channel));
+    }
+    let response = match (*static_var::UREQ_AGENT).get(request_url.as_str()).call() {
+        Ok(v) => v,
+        Err(_) => return None,
+    };
+    let response_json: UpdatePatchResp = match response.into_json() {
+        Ok(v) => v,
+        Err(_) => return None,
+    };
+
+    return Some(UpdatePatch {
+        download_url: response_json.download_url,
+        sha256: response_json.sha256.to_lowercase(),
+    });
+}
+
+pub struct ResourceSize {
+    pub download_size: u64,
+    pub unpacked_size: u64,
+}
+
+pub fn try_get_resource_size(resource_id: i32) -> Result<ResourceSize, String> {
+    #[derive(Deserialize)]
+    struct ResourceSizeResp {
+        download_size: u64,
+        unpacked_size: u64,
+    }
+
+    let request_url = format!("https://api.igame.ml/resource/{}/size", resource_id);
+    let response = (*static_var::UREQ_AGENT)
+        .get(request_url.as_str())
+        .call()
+        .map_err(describe_api_error)?;
+    let response_json: ResourceSizeResp = response
+        .into_json()
+        .map_err(|e| format!("反序列化响应失败：{}\n{:?}", request_url, e))?;
+
+    return Ok(ResourceSize {
+        download_size: response_json.download_size,
+        unpacked_size: response_json.unpacked_size,
+    });
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use crate::config::{get_config_value, set_config_value};
+use crate::static_var;
+use crate::version::Version;
+
+// 用户选择的更新通道，以及当前安装的版本来自哪个通道
+const CHANNEL_CONFIG_NAME: &str = "UpdateChannel";
+const INSTALLED_CHANNEL_CONFIG_NAME: &str = "InstalledChannel";
+pub const CHANNEL_ARG: &str = "--update-channel";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl fmt::Display for UpdateChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateChannel::Stable => write!(f, "stable"),
+            UpdateChannel::Beta => write!(f, "beta"),
+            UpdateChannel::Nightly => write!(f, "nightly"),
+        }
+    }
+}
+
+impl UpdateChannel {
+    pub fn parse(value: &str) -> Option<UpdateChannel> {
+        match value.trim().to_lowercase().as_str() {
+            "stable" => return Some(UpdateChannel::Stable),
+            "beta" => return Some(UpdateChannel::Beta),
+            "nightly" => return Some(UpdateChannel::Nightly),
+            _ => return None,
+        }
+    }
+
+    /// 重启到新版本时用来保留命令行指定的通道
+    pub fn to_arg(&self) -> String {
+        return format!("{}={}", CHANNEL_ARG, self);
+    }
+}
+
+fn get_cli_channel() -> Option<UpdateChannel> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == CHANNEL_ARG {
+            return iter.next().and_then(|v| UpdateChannel::parse(v));
+        }
+        if let Some(v) = arg.strip_prefix(&format!("{}=", CHANNEL_ARG)) {
+            return UpdateChannel::parse(v);
+        }
+    }
+    return None;
+}
+
+/// 优先级：命令行 > 设置 > 文件末尾的启动信息 > 正式版
+/// 启动信息里的channel同时也是推广渠道，只有是通道名称时才作为更新通道
+pub fn get_update_channel() -> UpdateChannel {
+    if let Some(v) = get_cli_channel() {
+        return v;
+    }
+    if let Some(v) = get_config_value(CHANNEL_CONFIG_NAME).and_then(|v| UpdateChannel::parse(&v)) {
+        return v;
+    }
+    if let Ok(payload) = static_var::LAUNCH_PAYLOAD.try_read() {
+        if let Some(v) = payload
+            .channel
+            .as_ref()
+            .and_then(|v| UpdateChannel::parse(v))
+        {
+            return v;
+        }
+    }
+    return UpdateChannel::Stable;
+}
+
+/// 没有记录时按自身版本号的预发布标识推断，例如0.3.0-beta.1属于beta通道
+pub fn get_installed_channel() -> UpdateChannel {
+    if let Some(v) =
+        get_config_value(INSTALLED_CHANNEL_CONFIG_NAME).and_then(|v| UpdateChannel::parse(&v))
+    {
+        return v;
+    }
+    let version = match Version::parse(env!("CARGO_PKG_VERSION")) {
+        Ok(v) => v,
+        Err(_) => return UpdateChannel::Stable,
+    };
+    return match version.pre_release.first() {
+        Some(v) => UpdateChannel::parse(v).unwrap_or(UpdateChannel::Beta),
+        None => UpdateChannel::Stable,
+    };
+}
+
+pub fn set_installed_channel(channel: UpdateChannel) -> Result<(), String> {
+    return set_config_value(INSTALLED_CHANNEL_CONFIG_NAME, &channel.to_string());
+}
@@ -0,0 +1,35 @@
+use rand::Rng;
+
+use crate::platform::system_registry;
+use crate::registry::RegistryRoot;
+
+// 引导器自身的设置保存在当前用户的注册表下
+const CONFIG_KEY_PATH: &str = r"SOFTWARE\IGameBootstrapper";
+const INSTALLATION_ID_CONFIG_NAME: &str = "InstallationId";
+
+pub fn get_config_value(name: &str) -> Option<String> {
+    let value = system_registry().get_string(RegistryRoot::CurrentUser, CONFIG_KEY_PATH, name)?;
+
+    if value == "" {
+        return None;
+    }
+    return Some(value);
+}
+
+pub fn set_config_value(name: &str, value: &str) -> Result<(), String> {
+    return system_registry()
+        .set_string(RegistryRoot::CurrentUser, CONFIG_KEY_PATH, name, value)
+        .map_err(|e| format!("保存设置失败：{}\n{}", name, e));
+}
+
+/// 匿名的安装标识，第一次使用时随机生成，只用于分阶段推送等统计
+pub fn get_installation_id() -> String {
+    if let Some(v) = get_config_value(INSTALLATION_ID_CONFIG_NAME) {
+        return v;
+    }
+    let installation_id: String = (0..32)
+        .map(|_| format!("{:x}", rand::thread_rng().gen_range(0..16)))
+        .collect();
+    let _ = set_config_value(INSTALLATION_ID_CONFIG_NAME, &installation_id);
+    return installation_id;
+}
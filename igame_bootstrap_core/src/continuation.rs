@@ -0,0 +1,233 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::channel::CHANNEL_ARG;
+use crate::static_var;
+use crate::time::unix_timestamp;
+use crate::trailer::LaunchPayload;
+use crate::workspace::get_workspace_root_path;
+
+// 更新后重启时传给新进程的参数，指向保存本次运行状态的文件
+const CONTINUATION_ARG: &str = "--continue";
+const CONTINUATION_FILE_PREFIX: &str = "continue-";
+const CONTINUATION_TOKEN_LEN: usize = 16;
+// 重启超过这个时间后状态不再沿用
+const CONTINUATION_MAX_AGE_SECONDS: u64 = 10 * 60;
+
+#[derive(Serialize, Deserialize)]
+pub struct Continuation {
+    pub launch_payload: LaunchPayload,
+    pub log_session: String,
+    pub created_at: u64,
+}
+
+fn continuation_file_path(root_path: &PathBuf, token: &str) -> PathBuf {
+    let mut file_path = root_path.clone();
+    file_path.push(format!("{}{}.json", CONTINUATION_FILE_PREFIX, token));
+    return file_path;
+}
+
+fn find_arg_value(args: &[String], name: &str) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == name {
+            return iter.next().cloned();
+        }
+        if let Some(v) = arg.strip_prefix(&format!("{}=", name)) {
+            return Some(v.to_string());
+        }
+    }
+    return None;
+}
+
+fn strip_arg(args: Vec<String>, name: &str) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == name {
+            iter.next();
+            continue;
+        }
+        if arg.starts_with(&format!("{}=", name)) {
+            continue;
+        }
+        result.push(arg);
+    }
+    return result;
+}
+
+/// 本次启动的原始参数，不包括上次重启时附加的参数，通道由重启前的进程另外指定
+pub fn forwarded_args() -> Vec<String> {
+    return strip_forwarded_args(std::env::args().skip(1).collect());
+}
+
+fn strip_forwarded_args(args: Vec<String>) -> Vec<String> {
+    return strip_arg(strip_arg(args, CONTINUATION_ARG), CHANNEL_ARG);
+}
+
+/// 保存本次运行的状态，返回需要传给新进程的参数
+pub fn write_continuation() -> Result<String, String> {
+    let continuation = Continuation {
+        launch_payload: match static_var::LAUNCH_PAYLOAD.try_read() {
+            Ok(v) => v.clone(),
+            Err(_) => Default::default(),
+        },
+        log_session: match static_var::LOG_SESSION.try_read() {
+            Ok(v) => v.clone(),
+            Err(_) => String::new(),
+        },
+        created_at: unix_timestamp(),
+    };
+    return save_continuation(&get_workspace_root_path(), &continuation);
+}
+
+fn save_continuation(root_path: &PathBuf, continuation: &Continuation) -> Result<String, String> {
+    let token: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(CONTINUATION_TOKEN_LEN)
+        .map(char::from)
+        .collect();
+    let file_path = continuation_file_path(root_path, &token);
+    let content = match serde_json::to_string(continuation) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("序列化运行状态失败\n{:?}", e)),
+    };
+    match fs::create_dir_all(root_path).and_then(|_| fs::write(&file_path, content)) {
+        Err(e) => return Err(format!("保存运行状态失败：{:?}\n{:?}", file_path, e)),
+        _ => {}
+    };
+
+    return Ok(format!("{}={}", CONTINUATION_ARG, token));
+}
+
+/// 读取后删除状态文件，同一个状态只能沿用一次
+pub fn take_continuation() -> Option<Continuation> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    return take_continuation_from(&args, &get_workspace_root_path(), unix_timestamp());
+}
+
+fn take_continuation_from(args: &[String], root_path: &PathBuf, now: u64) -> Option<Continuation> {
+    let token = find_arg_value(args, CONTINUATION_ARG)?;
+    // 令牌会拼进文件名，只接受写入时生成的格式
+    if token.len() != CONTINUATION_TOKEN_LEN || !token.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let file_path = continuation_file_path(root_path, &token);
+    let content = fs::read_to_string(&file_path).ok();
+    let _ = fs::remove_file(&file_path);
+    let continuation: Continuation = serde_json::from_str(&content?).ok()?;
+    if now.saturating_sub(continuation.created_at) > CONTINUATION_MAX_AGE_SECONDS {
+        return None;
+    }
+    return Some(continuation);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        return values.iter().map(|v| v.to_string()).collect();
+    }
+
+    // 每个测试使用自己的目录，避免并行运行时互相影响
+    fn root_path(name: &str) -> PathBuf {
+        let root_path = std::env::temp_dir().join(format!(
+            "igb-continuation-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&root_path);
+        return root_path;
+    }
+
+    fn continuation(created_at: u64) -> Continuation {
+        return Continuation {
+            launch_payload: LaunchPayload::from_resource_id(13),
+            log_session: "session".to_string(),
+            created_at,
+        };
+    }
+
+    #[test]
+    fn forwarded_args_drop_restart_args() {
+        let forwarded = strip_forwarded_args(args(&[
+            "--dry-run",
+            "--continue=abc",
+            "--update-channel",
+            "beta",
+            r"--log-dir=C:\logs\",
+            "--continue",
+            "def",
+            "--update-channel=dev",
+        ]));
+        assert_eq!(forwarded, args(&["--dry-run", r"--log-dir=C:\logs\"]));
+    }
+
+    #[test]
+    fn continuation_is_taken_once() {
+        let root_path = root_path("once");
+        let arg = save_continuation(&root_path, &continuation(1000)).unwrap();
+
+        let taken = take_continuation_from(&args(&["--dry-run", &arg]), &root_path, 1000).unwrap();
+        assert_eq!(taken.launch_payload, LaunchPayload::from_resource_id(13));
+        assert_eq!(taken.log_session, "session");
+        assert!(take_continuation_from(&args(&[&arg]), &root_path, 1000).is_none());
+
+        // 参数和值分开传入
+        let arg = save_continuation(&root_path, &continuation(1000)).unwrap();
+        let token = arg.split_once('=').unwrap().1;
+        assert!(
+            take_continuation_from(&args(&[CONTINUATION_ARG, token]), &root_path, 1000).is_some()
+        );
+    }
+
+    #[test]
+    fn expired_continuation_is_ignored() {
+        let root_path = root_path("expired");
+        let arg = save_continuation(&root_path, &continuation(1000)).unwrap();
+        let now = 1001 + CONTINUATION_MAX_AGE_SECONDS;
+        assert!(take_continuation_from(&args(&[&arg]), &root_path, now).is_none());
+        // 过期的状态文件同样被删除
+        assert_eq!(fs::read_dir(&root_path).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn malformed_continuation_is_ignored() {
+        let root_path = root_path("malformed");
+        fs::create_dir_all(&root_path).unwrap();
+        let token = "A".repeat(CONTINUATION_TOKEN_LEN);
+        let file_path = continuation_file_path(&root_path, &token);
+        fs::write(&file_path, "{not json").unwrap();
+        let arg = format!("{}={}", CONTINUATION_ARG, token);
+        assert!(take_continuation_from(&args(&[&arg]), &root_path, 1000).is_none());
+        assert!(!file_path.exists());
+    }
+
+    #[test]
+    fn invalid_tokens_are_rejected() {
+        let root_path = root_path("tokens");
+        let arg = save_continuation(&root_path, &continuation(1000)).unwrap();
+        let token = arg.split_once('=').unwrap().1.to_string();
+        let tokens = [
+            token[..CONTINUATION_TOKEN_LEN - 1].to_string(),
+            format!("{}A", token),
+            format!("../{}", &token[3..]),
+            String::new(),
+        ];
+        for token in tokens.iter() {
+            let arg = format!("{}={}", CONTINUATION_ARG, token);
+            assert!(
+                take_continuation_from(&args(&[&arg]), &root_path, 1000).is_none(),
+                "{}",
+                token
+            );
+        }
+        assert!(take_continuation_from(&args(&["--continue"]), &root_path, 1000).is_none());
+        assert!(take_continuation_from(&args(&[]), &root_path, 1000).is_none());
+        // 令牌不正确时不会删除状态文件
+        assert!(take_continuation_from(&args(&[&arg]), &root_path, 1000).is_some());
+    }
+}
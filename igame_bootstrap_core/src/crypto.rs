@@ -0,0 +1,45 @@
+use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+use ring::digest::{Context, SHA256};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+pub fn encrypt_message(message: &str) -> String {
+    type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+
+    let key: Vec<u8> = base64::decode("DP/B868Op9Ataw0l2YGtaS822jt26XWv7e3vMVa5zFI=").unwrap();
+    let iv: Vec<u8> = base64::decode("3rqBYyUB02E5HLOCI2i/2A==").unwrap();
+    let encryptor = Aes256CbcEnc::new_from_slices(key.as_slice(), iv.as_slice()).unwrap();
+    let ciphertext = encryptor.encrypt_padded_vec_mut::<Pkcs7>(message.as_bytes());
+    let content = base64::encode(ciphertext);
+
+    return content;
+}
+
+/// 返回小写十六进制的SHA-256
+pub fn file_sha256(path: &PathBuf) -> Result<String, String> {
+    let mut file = match fs::File::open(path) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("打开文件失败：{:?}\n{:?}", path, e)),
+    };
+    let mut context = Context::new(&SHA256);
+    let mut buffer: [u8; 8192] = [0; 8192];
+    loop {
+        let read_size = match file.read(&mut buffer) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("读取文件失败：{:?}\n{:?}", path, e)),
+        };
+        if read_size == 0 {
+            break;
+        }
+        context.update(&buffer[0..read_size]);
+    }
+
+    let digest = context.finish();
+    let hash: Vec<String> = digest
+        .as_ref()
+        .iter()
+        .map(|v| format!("{:02x}", v))
+        .collect();
+    return Ok(hash.join(""));
+}
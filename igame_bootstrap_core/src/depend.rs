@@ -0,0 +1,615 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::api::{get_update_info, peek_update_info};
+use crate::channel::UpdateChannel;
+use crate::disk::{format_size, is_same_volume};
+use crate::file::{extract_tzst, try_remove_path, try_restore_dir, try_swap_dir};
+use crate::net_framework;
+use crate::plan::InstallPlan;
+use crate::platform::{FileVersion, SpecialFolders, SystemPlatform, Volumes};
+use crate::registry::{Registry, RegistryRoot};
+use crate::version::compare_version;
+use crate::webview2;
+use crate::workspace::{get_alternative_root_path, get_workspace_dir_path, try_relocate_workspace};
+
+/// 按安装顺序排列
+pub const DEPEND_NAMES: [&str; 3] = [".NET框架 4.8", "WebView2", IGAME_INSTALLER_NAME];
+pub const IGAME_INSTALLER_NAME: &str = "IGame安装器";
+// 安装器及其暂存、备份目录都在Program Files\Infinite Dreams下
+const IGAME_INSTALLER_DIR_NAME: &str = "IGameInstaller";
+const IGAME_INSTALLER_STAGING_DIR_NAME: &str = "IGameInstaller_staging";
+const IGAME_INSTALLER_BACKUP_DIR_NAME: &str = "IGameInstaller_backup";
+const IGAME_INSTALLER_EXE_NAME: &str = "IGameInstaller.exe";
+// 安装器版本依次从版本文件、exe的版本资源、注册表读取
+const IGAME_INSTALLER_VERSION_FILE_NAME: &str = "version.txt";
+const IGAME_INSTALLER_REG_PATH: &str = r"SOFTWARE\Infinite Dreams\IGameInstaller";
+// 低于这个版本的运行环境需要升级，.NET框架的要求见net_framework
+const WEBVIEW2_MIN_VERSION: &str = "100.0.1185";
+// 预留给安装程序自身运行的空间
+const SPACE_MARGIN: u64 = 64 * 1024 * 1024;
+
+fn get_igame_installer_path(dir_name: &str) -> PathBuf {
+    let mut dir_path = SystemPlatform.program_files_dir();
+    dir_path.push("Infinite Dreams");
+    dir_path.push(dir_name);
+    return dir_path;
+}
+
+pub fn get_igame_installer_dir_path() -> PathBuf {
+    return get_igame_installer_path(IGAME_INSTALLER_DIR_NAME);
+}
+
+fn igame_installer_is_valid(dir_path: &PathBuf) -> bool {
+    let mut exe_path = dir_path.clone();
+    exe_path.push(IGAME_INSTALLER_EXE_NAME);
+    return match std::fs::metadata(&exe_path) {
+        Ok(v) => v.is_file() && v.len() != 0,
+        Err(_) => false,
+    };
+}
+
+fn get_igame_installer_version(dir_path: &PathBuf, registry: &dyn Registry) -> Option<String> {
+    let mut version_file_path = dir_path.clone();
+    version_file_path.push(IGAME_INSTALLER_VERSION_FILE_NAME);
+    if let Ok(v) = std::fs::read_to_string(&version_file_path) {
+        let v = v.trim();
+        if v != "" {
+            return Some(v.to_string());
+        }
+    }
+
+    let mut exe_path = dir_path.clone();
+    exe_path.push(IGAME_INSTALLER_EXE_NAME);
+    if let Some(v) = SystemPlatform.get_file_version(&exe_path) {
+        // 没有设置版本资源时为0.0.0.0
+        if v != "0.0.0.0" {
+            return Some(v);
+        }
+    }
+
+    let version = registry.get_string(
+        RegistryRoot::LocalMachine,
+        IGAME_INSTALLER_REG_PATH,
+        "Version",
+    )?;
+    if version != "" {
+        return Some(version);
+    }
+    return None;
+}
+
+// 读不到本地版本或最新版本时不认为过时，避免每次启动都重新安装
+fn igame_installer_is_outdated(
+    dir_path: &PathBuf,
+    registry: &dyn Registry,
+    read_only: bool,
+) -> bool {
+    let installed_version = match get_igame_installer_version(dir_path, registry) {
+        Some(v) => v,
+        None => return false,
+    };
+    let update_info = if read_only {
+        peek_update_info(12, &UpdateChannel::Stable)
+    } else {
+        get_update_info(12, &UpdateChannel::Stable)
+    };
+    let update_info = match update_info {
+        Some(v) => v,
+        None => return false,
+    };
+    return match compare_version(&installed_version, &update_info.latest_version) {
+        Ok(v) => v == std::cmp::Ordering::Less,
+        Err(_) => false,
+    };
+}
+
+// 安装包里没有版本文件时写入本次安装的版本，供下次启动比较
+fn write_igame_installer_version(dir_path: &PathBuf) {
+    let mut version_file_path = dir_path.clone();
+    version_file_path.push(IGAME_INSTALLER_VERSION_FILE_NAME);
+    if version_file_path.exists() {
+        return;
+    }
+    if let Some(v) = get_update_info(12, &UpdateChannel::Stable) {
+        let _ = std::fs::write(&version_file_path, v.latest_version);
+    }
+}
+
+/// 上次安装中途被打断时，还原备份并清理残留的暂存目录
+/// 会修改安装目录，需要在检测之前单独调用
+pub fn recover_igame_installer() {
+    recover_igame_installer_in(
+        &get_igame_installer_dir_path(),
+        &get_igame_installer_path(IGAME_INSTALLER_STAGING_DIR_NAME),
+        &get_igame_installer_path(IGAME_INSTALLER_BACKUP_DIR_NAME),
+    );
+}
+
+fn recover_igame_installer_in(dst_dir: &PathBuf, staging_dir: &PathBuf, backup_dir: &PathBuf) {
+    if backup_dir.exists() {
+        if igame_installer_is_valid(dst_dir) {
+            let _ = try_remove_path(backup_dir);
+        } else if igame_installer_is_valid(backup_dir) {
+            let _ = try_restore_dir(backup_dir, dst_dir);
+        }
+    }
+    let _ = try_remove_path(staging_dir);
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct DependStatus {
+    /// 读不到或无效时为None
+    pub installed_version: Option<String>,
+    pub required_version: Option<String>,
+    pub satisfied: bool,
+}
+
+impl DependStatus {
+    fn from_versions(installed_version: Option<String>, required_version: &str) -> DependStatus {
+        let satisfied = match &installed_version {
+            Some(v) => match compare_version(v, required_version) {
+                Ok(o) => o != std::cmp::Ordering::Less,
+                Err(_) => false,
+            },
+            None => false,
+        };
+        return DependStatus {
+            installed_version,
+            required_version: Some(required_version.to_string()),
+            satisfied,
+        };
+    }
+}
+
+/// read_only为true时不写更新检查缓存，供--dry-run使用
+pub fn get_depend_status(name: &str, registry: &dyn Registry, read_only: bool) -> DependStatus {
+    match name {
+        ".NET框架 4.8" => {
+            let required_version = net_framework::get_required_version();
+            return DependStatus {
+                installed_version: net_framework::get_installed_version(registry),
+                satisfied: match net_framework::get_installed_release(registry) {
+                    Some(v) => net_framework::release_satisfies(v, &required_version),
+                    None => false,
+                },
+                required_version: Some(required_version),
+            };
+        }
+        "WebView2" => {
+            return DependStatus::from_versions(
+                webview2::get_installed_runtime(registry).map(|v| v.version),
+                WEBVIEW2_MIN_VERSION,
+            );
+        }
+        IGAME_INSTALLER_NAME => {
+            let dst_dir = get_igame_installer_dir_path();
+            // 版本过时的安装器按未安装处理，重新安装时原地替换
+            return DependStatus {
+                installed_version: get_igame_installer_version(&dst_dir, registry),
+                required_version: None,
+                satisfied: igame_installer_is_valid(&dst_dir)
+                    && !igame_installer_is_outdated(&dst_dir, registry, read_only),
+            };
+        }
+        _ => {
+            return DependStatus {
+                installed_version: None,
+                required_version: None,
+                satisfied: false,
+            };
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct DependDetection {
+    pub name: String,
+    pub status: DependStatus,
+}
+
+/// 按DEPEND_NAMES的顺序检测所有依赖
+pub fn detect_depends(registry: &dyn Registry, read_only: bool) -> Vec<DependDetection> {
+    return DEPEND_NAMES
+        .iter()
+        .map(|name| DependDetection {
+            name: name.to_string(),
+            status: get_depend_status(name, registry, read_only),
+        })
+        .collect();
+}
+
+/// 下载之前检查工作目录和安装目录所在磁盘的剩余空间
+/// 大小来自补全后的安装计划，部分大小获取失败时跳过检查
+pub fn check_depends_space(plan: &InstallPlan) -> Result<(), String> {
+    match find_workspace_volume(
+        plan,
+        &SystemPlatform,
+        &get_igame_installer_dir_path(),
+        &get_workspace_dir_path(),
+    )? {
+        Some(drive_root) => return try_relocate_workspace(&get_alternative_root_path(&drive_root)),
+        None => return Ok(()),
+    }
+}
+
+// 返回需要迁移工作目录时的目标磁盘，当前工作目录空间足够时返回None
+fn find_workspace_volume(
+    plan: &InstallPlan,
+    volumes: &dyn Volumes,
+    install_dir: &PathBuf,
+    workspace_dir: &PathBuf,
+) -> Result<Option<PathBuf>, String> {
+    if !plan.sizes_resolved() {
+        return Ok(None);
+    }
+    let workspace_needed: u64 = SPACE_MARGIN + plan.workspace_size();
+    let install_needed: u64 = plan.install_dir_size();
+
+    // 安装目录本身空间不足时，换临时目录也无济于事
+    if install_needed != 0 {
+        let install_free = volumes.get_free_space(install_dir)?;
+        if install_free < install_needed {
+            return Err(format!(
+                "安装目录所在磁盘空间不足：需要{}，可用{}，还差{}\n请清理磁盘后重试",
+                format_size(install_needed),
+                format_size(install_free),
+                format_size(install_needed - install_free)
+            ));
+        }
+    }
+
+    let workspace_free = volumes.get_free_space(workspace_dir)?;
+    let mut needed = workspace_needed;
+    if is_same_volume(install_dir, workspace_dir) {
+        needed += install_needed;
+    }
+    if workspace_free >= needed {
+        return Ok(None);
+    }
+
+    // 临时目录空间不足，换到其他有足够空间的磁盘
+    for drive_root in volumes.list_fixed_drive_roots() {
+        if is_same_volume(&drive_root, workspace_dir) {
+            continue;
+        }
+        let free = match volumes.get_free_space(&drive_root) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let mut needed = workspace_needed;
+        if is_same_volume(install_dir, &drive_root) {
+            needed += install_needed;
+        }
+        if free >= needed {
+            return Ok(Some(drive_root));
+        }
+    }
+
+    return Err(format!(
+        "临时目录所在磁盘空间不足：需要{}，可用{}，还差{}\n请清理磁盘后重试",
+        format_size(needed),
+        format_size(workspace_free),
+        format_size(needed - workspace_free)
+    ));
+}
+
+/// 运行安装程序并等待结束，退出码不为0时返回错误
+pub fn run_installer(dir_path: &PathBuf, exe_name: &str, args: &[String]) -> Result<(), String> {
+    let mut installer_path = dir_path.clone();
+    installer_path.push(exe_name);
+    let output = match std::process::Command::new(&installer_path)
+        .args(args)
+        .output()
+    {
+        Ok(v) => v,
+        Err(e) => return Err(format!("启动安装程序失败：{:?}\n{:?}", installer_path, e)),
+    };
+
+    if !output.status.success() {
+        return Err(format!(
+            "{}报告了一个错误: {}",
+            exe_name,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    return Ok(());
+}
+
+/// 先解压到暂存目录，校验通过后再整体替换
+pub fn replace_igame_installer(tzst_path: &PathBuf) -> Result<(), String> {
+    let dst_dir = get_igame_installer_dir_path();
+    let staging_dir = get_igame_installer_path(IGAME_INSTALLER_STAGING_DIR_NAME);
+    let backup_dir = get_igame_installer_path(IGAME_INSTALLER_BACKUP_DIR_NAME);
+
+    try_remove_path(&staging_dir)?;
+    match extract_tzst(tzst_path, &staging_dir) {
+        Err(e) => {
+            let _ = try_remove_path(&staging_dir);
+            return Err(e);
+        }
+        _ => {}
+    };
+    try_remove_path(tzst_path)?;
+    if !igame_installer_is_valid(&staging_dir) {
+        let _ = try_remove_path(&staging_dir);
+        return Err(format!(
+            "IGame安装器校验失败：{}不存在",
+            IGAME_INSTALLER_EXE_NAME
+        ));
+    }
+
+    try_swap_dir(&staging_dir, &dst_dir, &backup_dir)?;
+    if !igame_installer_is_valid(&dst_dir) {
+        try_restore_dir(&backup_dir, &dst_dir)?;
+        return Err("IGame安装器替换后校验失败，已还原旧版本".to_string());
+    }
+    try_remove_path(&backup_dir)?;
+    write_igame_installer_version(&dst_dir);
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::ProviderGroup;
+    use crate::plan::{DependPlan, ExtractTarget, InstallStep, PlannedStep};
+    use crate::registry::FakeRegistry;
+
+    fn status(name: &str, fixture: &str) -> DependStatus {
+        return get_depend_status(name, &FakeRegistry::from_fixture(fixture).unwrap(), true);
+    }
+
+    #[test]
+    fn net_framework_status() {
+        let not_installed = status(DEPEND_NAMES[0], "");
+        assert_eq!(not_installed.installed_version, None);
+        assert_eq!(not_installed.required_version.as_deref(), Some("4.8"));
+        assert!(!not_installed.satisfied);
+
+        let cases = [
+            ("00070bf6", "4.7.2", false),
+            ("00080ea8", "4.8", true),
+            ("00080ff4", "4.8", true),
+            ("00082348", "4.8.1", true),
+        ];
+        for (release, version, satisfied) in cases {
+            let detected = status(
+                DEPEND_NAMES[0],
+                &format!(
+                    "[HKLM\\SOFTWARE\\Microsoft\\NET Framework Setup\\NDP\\v4\\Full]\n\"Release\"=dword:{}",
+                    release
+                ),
+            );
+            assert_eq!(detected.installed_version.as_deref(), Some(version));
+            assert_eq!(detected.satisfied, satisfied, "{}", release);
+        }
+    }
+
+    #[test]
+    fn webview2_status() {
+        let path = r"SOFTWARE\Microsoft\EdgeUpdate\Clients\{F3017226-FE2A-4295-8BDF-00C3A9A7E4C5}";
+        let not_installed = status(DEPEND_NAMES[1], "");
+        assert_eq!(not_installed.installed_version, None);
+        assert_eq!(
+            not_installed.required_version.as_deref(),
+            Some(WEBVIEW2_MIN_VERSION)
+        );
+        assert!(!not_installed.satisfied);
+
+        let cases = [
+            ("HKLM32", "0.0.0.0", None, false),
+            ("HKLM32", "99.0.1150.55", Some("99.0.1150.55"), false),
+            ("HKLM64", "100.0.1185.36", Some("100.0.1185.36"), true),
+            ("HKCU", "110.0.1587.41", Some("110.0.1587.41"), true),
+        ];
+        for (root, pv, version, satisfied) in cases {
+            let detected = status(
+                DEPEND_NAMES[1],
+                &format!("[{}\\{}]\n\"pv\"=\"{}\"", root, path, pv),
+            );
+            assert_eq!(detected.installed_version.as_deref(), version);
+            assert_eq!(detected.satisfied, satisfied, "{} {}", root, pv);
+        }
+    }
+
+    const MB: u64 = 1024 * 1024;
+
+    // 按路径前缀查找所在卷的剩余空间
+    struct FakeVolumes {
+        free: Vec<(&'static str, u64)>,
+    }
+
+    impl Volumes for FakeVolumes {
+        fn get_free_space(&self, path: &PathBuf) -> Result<u64, String> {
+            return match self.free.iter().find(|(root, _)| path.starts_with(root)) {
+                Some((_, v)) => Ok(*v),
+                None => Err(format!("获取剩余空间失败：{:?}", path)),
+            };
+        }
+
+        fn list_fixed_drive_roots(&self) -> Vec<PathBuf> {
+            return self
+                .free
+                .iter()
+                .map(|(root, _)| PathBuf::from(root))
+                .collect();
+        }
+    }
+
+    fn step(step: InstallStep) -> PlannedStep {
+        return PlannedStep {
+            step,
+            reason: String::new(),
+        };
+    }
+
+    // 下载100MB，解压到工作目录200MB，解压到安装目录50MB
+    fn sized_plan(download_size: Option<u64>) -> InstallPlan {
+        return InstallPlan {
+            os_arch: 64,
+            provider_group: ProviderGroup::Fast,
+            depends: vec![DependPlan {
+                name: IGAME_INSTALLER_NAME.to_string(),
+                installed_version: None,
+                required_version: None,
+                reason: String::new(),
+                steps: vec![
+                    step(InstallStep::Download {
+                        resource_id: 12,
+                        file_name: "a.tzst".to_string(),
+                        url: None,
+                        size: download_size,
+                    }),
+                    step(InstallStep::Extract {
+                        file_name: "a.tzst".to_string(),
+                        target: ExtractTarget::Workspace,
+                        size: Some(200 * MB),
+                    }),
+                    step(InstallStep::Extract {
+                        file_name: "a.tzst".to_string(),
+                        target: ExtractTarget::IGameInstallerDir,
+                        size: Some(50 * MB),
+                    }),
+                ],
+            }],
+        };
+    }
+
+    fn find(plan: &InstallPlan, free: Vec<(&'static str, u64)>) -> Result<Option<PathBuf>, String> {
+        return find_workspace_volume(
+            plan,
+            &FakeVolumes { free },
+            &PathBuf::from("/install/IGameInstaller"),
+            &PathBuf::from("/workspace/run-1"),
+        );
+    }
+
+    #[test]
+    fn enough_space_keeps_workspace() {
+        let plan = sized_plan(Some(100 * MB));
+        // 工作目录需要64MB余量 + 300MB
+        let result = find(&plan, vec![("/install", 50 * MB), ("/workspace", 364 * MB)]);
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn install_dir_without_space_fails() {
+        let plan = sized_plan(Some(100 * MB));
+        let result = find(
+            &plan,
+            vec![("/install", 49 * MB), ("/workspace", 1024 * MB)],
+        );
+        assert!(result.unwrap_err().contains("安装目录所在磁盘空间不足"));
+    }
+
+    #[test]
+    fn workspace_moves_to_drive_with_space() {
+        let plan = sized_plan(Some(100 * MB));
+        let result = find(
+            &plan,
+            vec![
+                ("/install", 50 * MB),
+                ("/workspace", 363 * MB),
+                ("/small", 100 * MB),
+                ("/large", 1024 * MB),
+            ],
+        );
+        assert_eq!(result, Ok(Some(PathBuf::from("/large"))));
+    }
+
+    #[test]
+    fn no_drive_with_space_fails() {
+        let plan = sized_plan(Some(100 * MB));
+        let result = find(
+            &plan,
+            vec![
+                ("/install", 50 * MB),
+                ("/workspace", 300 * MB),
+                ("/small", 100 * MB),
+            ],
+        );
+        assert!(result.unwrap_err().contains("临时目录所在磁盘空间不足"));
+    }
+
+    #[test]
+    fn unknown_size_skips_check() {
+        let plan = sized_plan(None);
+        assert!(!plan.sizes_resolved());
+        assert_eq!(find(&plan, vec![]), Ok(None));
+        assert!(sized_plan(Some(100 * MB)).sizes_resolved());
+    }
+
+    // 返回安装目录、暂存目录和备份目录，exe为None时不创建对应目录
+    fn installer_dirs(
+        name: &str,
+        dst_exe: Option<&[u8]>,
+        backup_exe: Option<&[u8]>,
+    ) -> (PathBuf, PathBuf, PathBuf) {
+        let root_path =
+            std::env::temp_dir().join(format!("igb-depend-test-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&root_path);
+        let dst_dir = root_path.join(IGAME_INSTALLER_DIR_NAME);
+        let staging_dir = root_path.join(IGAME_INSTALLER_STAGING_DIR_NAME);
+        let backup_dir = root_path.join(IGAME_INSTALLER_BACKUP_DIR_NAME);
+        for (dir_path, exe) in [(&dst_dir, dst_exe), (&backup_dir, backup_exe)] {
+            if let Some(v) = exe {
+                std::fs::create_dir_all(dir_path).unwrap();
+                std::fs::write(dir_path.join(IGAME_INSTALLER_EXE_NAME), v).unwrap();
+            }
+        }
+        std::fs::create_dir_all(&staging_dir).unwrap();
+        return (dst_dir, staging_dir, backup_dir);
+    }
+
+    #[test]
+    fn recover_after_interrupted_swap() {
+        // 旧目录已挪到备份位置，新目录还没放进来
+        let (dst_dir, staging_dir, backup_dir) = installer_dirs("interrupted", None, Some(b"old"));
+        recover_igame_installer_in(&dst_dir, &staging_dir, &backup_dir);
+        assert_eq!(
+            std::fs::read(dst_dir.join(IGAME_INSTALLER_EXE_NAME)).unwrap(),
+            b"old"
+        );
+        assert!(!backup_dir.exists());
+        assert!(!staging_dir.exists());
+        let _ = std::fs::remove_dir_all(dst_dir.parent().unwrap());
+    }
+
+    #[test]
+    fn recover_replaces_invalid_installer() {
+        let (dst_dir, staging_dir, backup_dir) = installer_dirs("invalid", Some(b""), Some(b"old"));
+        recover_igame_installer_in(&dst_dir, &staging_dir, &backup_dir);
+        assert_eq!(
+            std::fs::read(dst_dir.join(IGAME_INSTALLER_EXE_NAME)).unwrap(),
+            b"old"
+        );
+        assert!(!backup_dir.exists());
+        let _ = std::fs::remove_dir_all(dst_dir.parent().unwrap());
+    }
+
+    #[test]
+    fn recover_drops_backup_after_finished_swap() {
+        let (dst_dir, staging_dir, backup_dir) =
+            installer_dirs("finished", Some(b"new"), Some(b"old"));
+        recover_igame_installer_in(&dst_dir, &staging_dir, &backup_dir);
+        assert_eq!(
+            std::fs::read(dst_dir.join(IGAME_INSTALLER_EXE_NAME)).unwrap(),
+            b"new"
+        );
+        assert!(!backup_dir.exists());
+        assert!(!staging_dir.exists());
+        let _ = std::fs::remove_dir_all(dst_dir.parent().unwrap());
+    }
+
+    #[test]
+    fn recover_keeps_invalid_backup() {
+        // 两边都无效时保留备份，不删除任何可能有用的文件
+        let (dst_dir, staging_dir, backup_dir) = installer_dirs("both-invalid", None, Some(b""));
+        recover_igame_installer_in(&dst_dir, &staging_dir, &backup_dir);
+        assert!(!dst_dir.exists());
+        assert!(backup_dir.exists());
+        assert!(!staging_dir.exists());
+        let _ = std::fs::remove_dir_all(dst_dir.parent().unwrap());
+    }
+}
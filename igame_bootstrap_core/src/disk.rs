@@ -0,0 +1,33 @@
+use std::path::{Component, PathBuf};
+
+pub fn format_size(size: u64) -> String {
+    let size = size as f64;
+    if size >= 1073741824.0 {
+        return format!("{:.2}GB", size / 1073741824.0);
+    } else if size >= 1048576.0 {
+        return format!("{:.1}MB", size / 1048576.0);
+    } else {
+        return format!("{:.0}KB", size / 1024.0);
+    }
+}
+
+/// 返回路径所在卷的根目录，例如C:\
+pub fn get_volume_root(path: &PathBuf) -> Option<PathBuf> {
+    match path.components().next() {
+        Some(Component::Prefix(prefix)) => {
+            let mut root_path = PathBuf::from(prefix.as_os_str());
+            root_path.push(Component::RootDir.as_os_str());
+            return Some(root_path);
+        }
+        _ => return None,
+    }
+}
+
+pub fn is_same_volume(path1: &PathBuf, path2: &PathBuf) -> bool {
+    match (get_volume_root(path1), get_volume_root(path2)) {
+        (Some(v1), Some(v2)) => {
+            return v1.to_string_lossy().to_lowercase() == v2.to_string_lossy().to_lowercase()
+        }
+        _ => return false,
+    }
+}
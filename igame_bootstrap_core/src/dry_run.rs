@@ -0,0 +1,361 @@
+use serde::Serialize;
+
+use crate::depend::detect_depends;
+use crate::disk::format_size;
+use crate::file::search_launch_payload;
+use crate::plan::{
+    compute_install_plan, try_resolve_install_plan, ExtractTarget, InstallPlan, InstallPolicy,
+    InstallStep,
+};
+use crate::platform::system_registry;
+use crate::static_var;
+use crate::system_info::os_is_ok;
+use crate::trailer::LaunchPayload;
+
+/// 只检测并输出安装计划，不下载也不安装，例如--dry-run或--dry-run=json
+pub const DRY_RUN_ARG: &str = "--dry-run";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DryRunFormat {
+    Text,
+    Json,
+}
+
+impl DryRunFormat {
+    pub fn parse(value: &str) -> Option<DryRunFormat> {
+        match value.to_lowercase().as_str() {
+            "text" => return Some(DryRunFormat::Text),
+            "json" => return Some(DryRunFormat::Json),
+            _ => return None,
+        }
+    }
+}
+
+/// 没有指定格式或格式不正确时输出文本
+pub fn get_dry_run_format() -> Option<DryRunFormat> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    return parse_dry_run_format(&args);
+}
+
+fn parse_dry_run_format(args: &[String]) -> Option<DryRunFormat> {
+    for arg in args.iter() {
+        if arg == DRY_RUN_ARG {
+            return Some(DryRunFormat::Text);
+        }
+        if let Some(v) = arg.strip_prefix(&format!("{}=", DRY_RUN_ARG)) {
+            return Some(DryRunFormat::parse(v).unwrap_or(DryRunFormat::Text));
+        }
+    }
+    return None;
+}
+
+#[derive(Serialize)]
+pub struct DryRunReport {
+    pub version: String,
+    pub os_supported: bool,
+    pub os_arch: u8,
+    /// 读取失败时为None，原因见launch_payload_error
+    pub launch_payload: Option<LaunchPayload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launch_payload_error: Option<String>,
+    /// 签名无效等原因忽略了其中的资源信息，正常启动时会上报
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launch_payload_warning: Option<String>,
+    pub install_plan: InstallPlan,
+}
+
+/// 不修改安装目录、不写缓存和日志、不上报，接口请求失败时返回错误
+pub fn build_dry_run_report() -> Result<DryRunReport, String> {
+    let (launch_payload, launch_payload_warning, launch_payload_error) =
+        match search_launch_payload() {
+            Ok((payload, warning)) => (Some(payload), warning, None),
+            Err(e) => (None, None, Some(e)),
+        };
+    let mut install_plan = compute_install_plan(
+        &detect_depends(system_registry(), true),
+        *static_var::OS_ARCH,
+        &InstallPolicy::default(),
+    )?;
+    try_resolve_install_plan(&mut install_plan)?;
+
+    return Ok(DryRunReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        os_supported: os_is_ok(system_registry()),
+        os_arch: *static_var::OS_ARCH,
+        launch_payload,
+        launch_payload_error,
+        launch_payload_warning,
+        install_plan,
+    });
+}
+
+fn format_optional_size(size: &Option<u64>) -> String {
+    match size {
+        Some(v) => return format_size(*v),
+        None => return "大小未知".to_string(),
+    }
+}
+
+fn format_step(step: &InstallStep) -> String {
+    match step {
+        InstallStep::Download {
+            resource_id,
+            file_name,
+            url,
+            size,
+        } => {
+            return format!(
+                "下载 {}（资源{}，{}）\n      {}",
+                file_name,
+                resource_id,
+                format_optional_size(size),
+                url.as_deref().unwrap_or("下载地址未获取")
+            );
+        }
+        InstallStep::Verify { file_name, size } => {
+            return format!("校验 {}（{}）", file_name, format_optional_size(size));
+        }
+        InstallStep::Extract {
+            file_name,
+            target,
+            size,
+        } => {
+            let target = match target {
+                ExtractTarget::Workspace => "工作目录",
+                ExtractTarget::IGameInstallerDir => "IGame安装器目录",
+            };
+            return format!(
+                "解压 {} 到{}（{}）",
+                file_name,
+                target,
+                format_optional_size(size)
+            );
+        }
+        InstallStep::Run { exe_name, args } => {
+            if args.is_empty() {
+                return format!("运行 {}", exe_name);
+            }
+            return format!("运行 {} {}", exe_name, args.join(" "));
+        }
+        InstallStep::PostVerify { name } => return format!("检测 {}", name),
+    }
+}
+
+pub fn format_dry_run_report(report: &DryRunReport) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    lines.push(format!(
+        "IGame引导器 v{} 安装计划（仅预览，不会下载或安装）",
+        report.version
+    ));
+    lines.push(format!(
+        "系统：{}，{}位",
+        if report.os_supported {
+            "满足要求"
+        } else {
+            "不满足要求"
+        },
+        report.os_arch
+    ));
+    match (&report.launch_payload, &report.launch_payload_error) {
+        (Some(v), _) => {
+            let resource_ids: Vec<String> = v.resource_ids.iter().map(|v| v.to_string()).collect();
+            lines.push(format!("启动信息：资源ID {}", resource_ids.join(",")));
+        }
+        (None, Some(e)) => lines.push(format!("启动信息：读取失败，{}", e)),
+        (None, None) => {}
+    };
+    if let Some(v) = &report.launch_payload_warning {
+        lines.push(format!("启动信息：{}", v));
+    }
+
+    let plan = &report.install_plan;
+    if plan.is_empty() {
+        lines.push("所有运行环境都已满足要求，将直接启动IGame安装器".to_string());
+        return lines.join("\n");
+    }
+
+    lines.push(format!("需要安装{}个运行环境：", plan.depends.len()));
+    for (i, depend) in plan.depends.iter().enumerate() {
+        lines.push(format!("{}. {}：{}", i + 1, depend.name, depend.reason));
+        for (j, planned) in depend.steps.iter().enumerate() {
+            lines.push(format!(
+                "   {}) {}\n      {}",
+                j + 1,
+                format_step(&planned.step),
+                planned.reason
+            ));
+        }
+    }
+    lines.push(format!(
+        "需要空间：工作目录{}，IGame安装器目录{}",
+        format_size(plan.workspace_size()),
+        format_size(plan.install_dir_size())
+    ));
+    lines.push("完成后启动IGame安装器".to_string());
+    return lines.join("\n");
+}
+
+pub fn render_dry_run_report(report: &DryRunReport, format: DryRunFormat) -> String {
+    match format {
+        DryRunFormat::Text => return format_dry_run_report(report),
+        DryRunFormat::Json => return serde_json::to_string_pretty(report).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::ProviderGroup;
+    use crate::plan::{DependPlan, PlannedStep};
+
+    #[test]
+    fn dry_run_format_from_args() {
+        let cases: [(&[&str], Option<DryRunFormat>); 8] = [
+            (&[], None),
+            (&["--silent"], None),
+            (&["--dry-run"], Some(DryRunFormat::Text)),
+            (&["--dry-run=json"], Some(DryRunFormat::Json)),
+            (&["--dry-run=JSON"], Some(DryRunFormat::Json)),
+            (&["--dry-run=text"], Some(DryRunFormat::Text)),
+            (&["--dry-run=xml"], Some(DryRunFormat::Text)),
+            (
+                &["--update-channel=beta", "--dry-run=json"],
+                Some(DryRunFormat::Json),
+            ),
+        ];
+        for (args, expected) in cases {
+            let args: Vec<String> = args.iter().map(|v| v.to_string()).collect();
+            assert_eq!(parse_dry_run_format(&args), expected, "{:?}", args);
+        }
+        // 前缀相同的其他参数不算
+        assert_eq!(parse_dry_run_format(&["--dry-run-json".to_string()]), None);
+    }
+
+    fn step(step: InstallStep, reason: &str) -> PlannedStep {
+        return PlannedStep {
+            step,
+            reason: reason.to_string(),
+        };
+    }
+
+    fn sample_report() -> DryRunReport {
+        return DryRunReport {
+            version: "0.2.6".to_string(),
+            os_supported: true,
+            os_arch: 64,
+            launch_payload: Some(LaunchPayload::from_resource_id(13)),
+            launch_payload_error: None,
+            launch_payload_warning: None,
+            install_plan: InstallPlan {
+                os_arch: 64,
+                provider_group: ProviderGroup::Fast,
+                depends: vec![DependPlan {
+                    name: "IGame安装器".to_string(),
+                    installed_version: Some("1.0.0".to_string()),
+                    required_version: None,
+                    reason: "已安装1.0.0，需要更新".to_string(),
+                    steps: vec![
+                        step(
+                            InstallStep::Download {
+                                resource_id: 12,
+                                file_name: "IGameInstaller.tzst".to_string(),
+                                url: Some("https://example.com/12".to_string()),
+                                size: Some(2 * 1024 * 1024),
+                            },
+                            "下载IGame安装器的安装包",
+                        ),
+                        step(
+                            InstallStep::Extract {
+                                file_name: "IGameInstaller.tzst".to_string(),
+                                target: ExtractTarget::IGameInstallerDir,
+                                size: None,
+                            },
+                            "校验后替换安装目录，失败时还原旧版本",
+                        ),
+                        step(
+                            InstallStep::PostVerify {
+                                name: "IGame安装器".to_string(),
+                            },
+                            "确认安装后满足要求",
+                        ),
+                    ],
+                }],
+            },
+        };
+    }
+
+    #[test]
+    fn text_report() {
+        let expected = "\
+IGame引导器 v0.2.6 安装计划（仅预览，不会下载或安装）
+系统：满足要求，64位
+启动信息：资源ID 13
+需要安装1个运行环境：
+1. IGame安装器：已安装1.0.0，需要更新
+   1) 下载 IGameInstaller.tzst（资源12，2.0MB）
+      https://example.com/12
+      下载IGame安装器的安装包
+   2) 解压 IGameInstaller.tzst 到IGame安装器目录（大小未知）
+      校验后替换安装目录，失败时还原旧版本
+   3) 检测 IGame安装器
+      确认安装后满足要求
+需要空间：工作目录2.0MB，IGame安装器目录0KB
+完成后启动IGame安装器";
+        assert_eq!(
+            render_dry_run_report(&sample_report(), DryRunFormat::Text).trim_end(),
+            expected
+        );
+    }
+
+    #[test]
+    fn json_report() {
+        let expected = r#"{
+  "version": "0.2.6",
+  "os_supported": true,
+  "os_arch": 64,
+  "launch_payload": {
+    "resource_ids": [
+      13
+    ]
+  },
+  "install_plan": {
+    "os_arch": 64,
+    "provider_group": "fast",
+    "depends": [
+      {
+        "name": "IGame安装器",
+        "installed_version": "1.0.0",
+        "required_version": null,
+        "reason": "已安装1.0.0，需要更新",
+        "steps": [
+          {
+            "action": "download",
+            "resource_id": 12,
+            "file_name": "IGameInstaller.tzst",
+            "url": "https://example.com/12",
+            "size": 2097152,
+            "reason": "下载IGame安装器的安装包"
+          },
+          {
+            "action": "extract",
+            "file_name": "IGameInstaller.tzst",
+            "target": "igame_installer_dir",
+            "size": null,
+            "reason": "校验后替换安装目录，失败时还原旧版本"
+          },
+          {
+            "action": "post_verify",
+            "name": "IGame安装器",
+            "reason": "确认安装后满足要求"
+          }
+        ]
+      }
+    ]
+  }
+}"#;
+        assert_eq!(
+            render_dry_run_report(&sample_report(), DryRunFormat::Json).trim_end(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::Write;
+
+use crate::crypto::encrypt_message;
+use crate::file::write_temp_file;
+use crate::net_framework::get_installed_version;
+use crate::platform::{system_registry, MessageBox, SystemPlatform};
+use crate::static_var;
+use crate::time::generate_timestamp;
+
+/// 按参数弹窗、写日志、上报错误，exit为true时退出程序
+pub fn process_error(message: String, open_box: bool, write_log: bool, upload: bool, exit: bool) {
+    if open_box {
+        match SystemPlatform.show_error("IGame引导程序错误", message.as_str()) {
+            Err(e) => process_error(e, false, true, true, true),
+            _ => {}
+        };
+    }
+    if write_log {
+        let log_session = match static_var::LOG_SESSION.try_read() {
+            Ok(v) => v.clone(),
+            Err(_) => String::new(),
+        };
+        let (_, mut error_file) = write_temp_file("IGameBootstrapError.log", true).unwrap();
+        error_file
+            .write_all(
+                format!("{} [{}]\n{}\n", generate_timestamp(), log_session, message).as_bytes(),
+            )
+            .unwrap();
+    }
+    if upload {
+        // 读取失败时不带启动信息，避免在错误处理中再次出错
+        let payload = match static_var::LAUNCH_PAYLOAD.try_read() {
+            Ok(v) => v.clone(),
+            Err(_) => Default::default(),
+        };
+        ureq::post("https://api.igame.ml/error/collect")
+            .set("Content-Type", "application/json")
+            .timeout(std::time::Duration::from_secs(10))
+            .send_json(ureq::json!({
+                "app_name": "IGameBootstrapper",
+                "app_version": env!("CARGO_PKG_VERSION"),
+                "resource_ids": payload.resource_ids,
+                "channel": payload.channel,
+                "attribution": payload.attribution,
+                "net_framework_version": get_installed_version(system_registry()),
+                "content": encrypt_message(message.as_str())
+            }))
+            .unwrap();
+    }
+    if exit {
+        crate::process::exit(1);
+    }
+}
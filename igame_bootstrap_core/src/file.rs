@@ -0,0 +1,483 @@
+use std::fs::{self, copy, create_dir_all, remove_dir_all, remove_file, rename};
+use std::io;
+use std::path::PathBuf;
+
+use crate::error::process_error;
+use crate::platform::{SpecialFolders, SystemPlatform};
+use crate::static_var;
+use crate::trailer::{read_launch_payload, LaunchPayload, PayloadSignature, SignedLaunchPayload};
+
+// pub fn path_to_string(path: &PathBuf) -> String {
+//     return path.clone().into_os_string().into_string().unwrap();
+// }
+
+// pub fn read_temp_file(name: &str) -> Result<(PathBuf, fs::File), std::io::Error> {
+//     let mut temp_path = std::env::temp_dir();
+//     temp_path.push(name);
+//     let temp_file = fs::File::options().read(true).open(temp_path.clone())?;
+//     return Ok((temp_path, temp_file));
+// }
+
+pub fn write_temp_file(name: &str, append: bool) -> Result<(PathBuf, fs::File), std::io::Error> {
+    let mut temp_path = SystemPlatform.temp_dir();
+    temp_path.push(name);
+    let temp_file = fs::File::options()
+        .write(true)
+        .truncate(!append)
+        .append(append)
+        .create(true)
+        .open(temp_path.clone())?;
+    return Ok((temp_path, temp_file));
+}
+
+/// 读取到的启动信息没有签名或签名无效时的处理方式
+pub enum SignaturePolicy {
+    Accept,
+    Reject,
+    FallbackToDefault,
+}
+
+// 去掉签名后改写成旧格式或版本2同样可以篡改资源信息，按签名无效处理
+const MISSING_SIGNATURE_POLICY: SignaturePolicy = SignaturePolicy::FallbackToDefault;
+// 签名无效说明文件被篡改，忽略其中的资源信息并上报
+const INVALID_SIGNATURE_POLICY: SignaturePolicy = SignaturePolicy::FallbackToDefault;
+
+/// 读取自身末尾的启动信息，忽略了其中的资源信息时上报原因
+pub fn try_search_launch_payload() -> LaunchPayload {
+    match search_launch_payload() {
+        Ok((payload, None)) => return payload,
+        Ok((payload, Some(warning))) => {
+            process_error(warning, false, true, true, false);
+            return payload;
+        }
+        Err(e) => {
+            process_error(format!("检索自身信息失败：{}", e), true, true, true, true);
+            return LaunchPayload::from_resource_id(0);
+        }
+    }
+}
+
+/// 不上报，忽略了其中的资源信息时同时返回原因，由调用方决定是否上报
+pub fn search_launch_payload() -> Result<(LaunchPayload, Option<String>), String> {
+    let self_exe_path = PathBuf::from((*static_var::CURRENT_EXE_PATH).clone());
+    return check_launch_payload(read_launch_payload(&self_exe_path)?);
+}
+
+/// 按签名策略得到实际使用的启动信息
+pub fn check_launch_payload(
+    signed_payload: SignedLaunchPayload,
+) -> Result<(LaunchPayload, Option<String>), String> {
+    let (policy, message) = match signed_payload.signature {
+        PayloadSignature::Valid => return Ok((signed_payload.payload, None)),
+        PayloadSignature::Missing => (MISSING_SIGNATURE_POLICY, "启动信息缺少签名"),
+        PayloadSignature::Invalid => (INVALID_SIGNATURE_POLICY, "启动信息签名无效，文件可能被篡改"),
+    };
+
+    match policy {
+        SignaturePolicy::Accept => return Ok((signed_payload.payload, None)),
+        SignaturePolicy::Reject => return Err(message.to_string()),
+        SignaturePolicy::FallbackToDefault => {
+            return Ok((
+                LaunchPayload::default(),
+                Some(format!(
+                    "{}，已忽略其中的资源信息\n{:?}",
+                    message, signed_payload.payload
+                )),
+            ));
+        }
+    }
+}
+
+pub fn try_copy_file(src_path: &PathBuf, dst_path: &PathBuf) -> Result<(), String> {
+    if src_path.exists() && src_path.is_file() {
+        if dst_path.exists() {
+            try_remove_path(dst_path)?;
+        }
+
+        match copy(src_path, dst_path) {
+            Err(e) => {
+                return Err(format!(
+                    "复制文件失败：{:?} -> {:?}\n{:?}",
+                    src_path, dst_path, e
+                ));
+            }
+            _ => {}
+        };
+    }
+
+    return Ok(());
+}
+
+pub fn try_move_file(src_path: &PathBuf, dst_path: &PathBuf) -> Result<(), String> {
+    if src_path.exists() && src_path.is_file() {
+        if dst_path.exists() {
+            try_remove_path(dst_path)?;
+        }
+
+        match rename(src_path, dst_path) {
+            Err(e) => {
+                return Err(format!(
+                    "移动文件失败：{:?} -> {:?}\n{:?}",
+                    src_path, dst_path, e
+                ));
+            }
+            _ => {}
+        };
+    }
+
+    return Ok(());
+}
+
+pub fn try_swap_dir(
+    new_dir_path: &PathBuf,
+    dst_dir_path: &PathBuf,
+    backup_dir_path: &PathBuf,
+) -> Result<(), String> {
+    // 先把旧目录挪到备份位置，新目录替换失败时再挪回来
+    try_remove_path(backup_dir_path)?;
+    let has_old = dst_dir_path.exists();
+    if has_old {
+        match rename(dst_dir_path, backup_dir_path) {
+            Err(e) => {
+                return Err(format!(
+                    "备份文件夹失败：{:?} -> {:?}\n{:?}",
+                    dst_dir_path, backup_dir_path, e
+                ));
+            }
+            _ => {}
+        };
+    }
+
+    match rename(new_dir_path, dst_dir_path) {
+        Err(e) => {
+            if has_old {
+                match rename(backup_dir_path, dst_dir_path) {
+                    Err(e2) => {
+                        return Err(format!(
+                            "替换文件夹失败：{:?} -> {:?}\n{:?}\n还原备份失败：{:?}",
+                            new_dir_path, dst_dir_path, e, e2
+                        ));
+                    }
+                    _ => {}
+                };
+            }
+            return Err(format!(
+                "替换文件夹失败：{:?} -> {:?}\n{:?}",
+                new_dir_path, dst_dir_path, e
+            ));
+        }
+        _ => {}
+    };
+
+    return Ok(());
+}
+
+pub fn try_restore_dir(backup_dir_path: &PathBuf, dst_dir_path: &PathBuf) -> Result<(), String> {
+    if !backup_dir_path.exists() {
+        return Ok(());
+    }
+    try_remove_path(dst_dir_path)?;
+    match rename(backup_dir_path, dst_dir_path) {
+        Err(e) => {
+            return Err(format!(
+                "还原文件夹失败：{:?} -> {:?}\n{:?}",
+                backup_dir_path, dst_dir_path, e
+            ));
+        }
+        _ => {}
+    };
+
+    return Ok(());
+}
+
+fn retry_remove_file(path: &PathBuf) -> io::Result<()> {
+    for _ in 1..20 {
+        match remove_file(path) {
+            Err(_) => {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                continue;
+            }
+            _ => return Ok(()),
+        }
+    }
+    remove_file(path)
+}
+
+fn retry_remove_dir_all(path: &PathBuf) -> io::Result<()> {
+    for _ in 1..20 {
+        match remove_dir_all(path) {
+            Err(_) => {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                continue;
+            }
+            _ => return Ok(()),
+        }
+    }
+    remove_dir_all(path)
+}
+
+pub fn try_remove_path(path: &PathBuf) -> Result<(), String> {
+    if path.exists() {
+        if path.is_file() {
+            match retry_remove_file(path) {
+                Err(e) => {
+                    return Err(format!("删除文件失败：{:?}\n{:?}", path, e));
+                }
+                _ => {}
+            };
+        } else if path.is_dir() {
+            match retry_remove_dir_all(path) {
+                Err(e) => {
+                    return Err(format!("删除文件夹失败：{:?}\n{:?}", path, e));
+                }
+                _ => {}
+            };
+        }
+    }
+
+    return Ok(());
+}
+
+// zstd允许的最大窗口，32位系统上为2^30（ZSTD_WINDOWLOG_MAX_32）
+const ZSTD_WINDOW_LOG_MAX: u32 = if cfg!(target_pointer_width = "64") {
+    31
+} else {
+    30
+};
+
+/// 以旧文件为字典解压zstd --patch-from生成的差分补丁
+pub fn apply_zstd_patch(
+    base_path: &PathBuf,
+    patch_path: &PathBuf,
+    dst_path: &PathBuf,
+) -> Result<(), String> {
+    let base = match fs::read(base_path) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(format!("读取旧版本文件失败：{:?}\n{:?}", base_path, e));
+        }
+    };
+    let patch_file = match fs::File::options().read(true).open(patch_path) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(format!("打开补丁文件失败：{:?}\n{:?}", patch_path, e));
+        }
+    };
+    let mut zstd_reader =
+        match zstd::stream::Decoder::with_dictionary(std::io::BufReader::new(patch_file), &base) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(format!("zstd读取补丁失败\n{:?}", e));
+            }
+        };
+    // --patch-from生成的补丁窗口可能覆盖整个旧文件
+    match zstd_reader.window_log_max(ZSTD_WINDOW_LOG_MAX) {
+        Err(e) => return Err(format!("zstd设置窗口大小失败\n{:?}", e)),
+        _ => {}
+    };
+    let mut dst_file = match fs::File::create(dst_path) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(format!("创建文件失败：{:?}\n{:?}", dst_path, e));
+        }
+    };
+    match std::io::copy(&mut zstd_reader, &mut dst_file) {
+        Err(e) => {
+            drop(dst_file);
+            let _ = try_remove_path(dst_path);
+            return Err(format!("zstd应用补丁失败\n{:?}", e));
+        }
+        _ => {}
+    };
+
+    return Ok(());
+}
+
+pub fn extract_tzst(tzst_path: &PathBuf, dir_path: &PathBuf) -> Result<(), String> {
+    match create_dir_all(dir_path) {
+        Ok(_) => {}
+        Err(e) => {
+            return Err(format!("创建文件夹失败：{:?}\n{:?}", dir_path, e));
+        }
+    };
+
+    let tzst_file = match fs::File::options().read(true).open(tzst_path) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(format!("打开tzst文件失败：{:?}\n{:?}", tzst_path, e));
+        }
+    };
+    let zstd_reader = match zstd::stream::Decoder::new(tzst_file) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(format!("zstd解压缩文件失败\n{:?}", e));
+        }
+    };
+    let mut archive = tar::Archive::new(zstd_reader);
+    match archive.unpack(dir_path) {
+        Ok(_) => {}
+        Err(e) => {
+            return Err(format!("tar解压缩文件失败\n{:?}", e));
+        }
+    };
+    drop(archive);
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trailer::tests::test_private_key;
+    use crate::trailer::{sign_launch_payload, write_launch_payload, write_signed_launch_payload};
+
+    fn temp_exe(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("igb-file-test-{}-{}", std::process::id(), name));
+        fs::write(&path, vec![0x5au8; 4096]).unwrap();
+        return path;
+    }
+
+    fn temp_dir_with(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("igb-file-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        create_dir_all(&path).unwrap();
+        fs::write(path.join("content.txt"), content).unwrap();
+        return path;
+    }
+
+    fn dir_content(path: &std::path::Path) -> String {
+        return fs::read_to_string(path.join("content.txt")).unwrap();
+    }
+
+    fn sample_payload() -> LaunchPayload {
+        return LaunchPayload {
+            resource_ids: vec![13, 9],
+            channel: Some("beta".to_string()),
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn zstd_patch_round_trip() {
+        let base: Vec<u8> = (0..256 * 1024).map(|i| (i * 7 % 251) as u8).collect();
+        let mut new = base.clone();
+        new[1000..1010].copy_from_slice(b"0123456789");
+        new.extend_from_slice(b"appended");
+        let base_path = temp_exe("patch-base");
+        let patch_path = temp_exe("patch");
+        let dst_path = temp_exe("patch-dst");
+        fs::write(&base_path, &base).unwrap();
+
+        let mut encoder = zstd::stream::Encoder::with_dictionary(Vec::new(), 19, &base).unwrap();
+        std::io::Write::write_all(&mut encoder, &new).unwrap();
+        let patch = encoder.finish().unwrap();
+        assert!(patch.len() < 1024);
+        fs::write(&patch_path, &patch).unwrap();
+
+        apply_zstd_patch(&base_path, &patch_path, &dst_path).unwrap();
+        assert_eq!(fs::read(&dst_path).unwrap(), new);
+
+        for path in [base_path, patch_path, dst_path] {
+            let _ = fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn valid_signature_keeps_payload() {
+        let path = temp_exe("valid");
+        let (data, signature) = sign_launch_payload(&sample_payload(), test_private_key()).unwrap();
+        write_signed_launch_payload(&path, &data, &signature).unwrap();
+
+        let signed_payload = read_launch_payload(&path).unwrap();
+        assert_eq!(signed_payload.signature, PayloadSignature::Valid);
+        let (payload, warning) = check_launch_payload(signed_payload).unwrap();
+        assert_eq!(payload, sample_payload());
+        assert_eq!(warning, None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn invalid_signature_falls_back_to_default() {
+        let path = temp_exe("invalid");
+        let (_, signature) = sign_launch_payload(&sample_payload(), test_private_key()).unwrap();
+        // 签名之后修改内容
+        let tampered = LaunchPayload::from_resource_id(666);
+        write_signed_launch_payload(&path, &serde_json::to_vec(&tampered).unwrap(), &signature)
+            .unwrap();
+
+        let signed_payload = read_launch_payload(&path).unwrap();
+        assert_eq!(signed_payload.signature, PayloadSignature::Invalid);
+        let (payload, warning) = check_launch_payload(signed_payload).unwrap();
+        assert_eq!(payload, LaunchPayload::default());
+        assert!(warning.unwrap().contains("签名无效"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_signature_falls_back_to_default() {
+        let path = temp_exe("missing");
+        write_launch_payload(&path, &sample_payload()).unwrap();
+
+        let signed_payload = read_launch_payload(&path).unwrap();
+        assert_eq!(signed_payload.signature, PayloadSignature::Missing);
+        let (payload, warning) = check_launch_payload(signed_payload).unwrap();
+        assert_eq!(payload, LaunchPayload::default());
+        assert!(warning.unwrap().contains("缺少签名"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn swap_dir_keeps_backup() {
+        let new_dir = temp_dir_with("swap-new", "new");
+        let dst_dir = temp_dir_with("swap-dst", "old");
+        let backup_dir = temp_dir_with("swap-backup", "stale");
+
+        try_swap_dir(&new_dir, &dst_dir, &backup_dir).unwrap();
+        assert!(!new_dir.exists());
+        assert_eq!(dir_content(&dst_dir), "new");
+        assert_eq!(dir_content(&backup_dir), "old");
+
+        try_restore_dir(&backup_dir, &dst_dir).unwrap();
+        assert!(!backup_dir.exists());
+        assert_eq!(dir_content(&dst_dir), "old");
+        // 没有备份时不做任何事
+        try_restore_dir(&backup_dir, &dst_dir).unwrap();
+        assert_eq!(dir_content(&dst_dir), "old");
+
+        let _ = fs::remove_dir_all(&dst_dir);
+    }
+
+    #[test]
+    fn swap_dir_without_old_dir() {
+        let new_dir = temp_dir_with("swap-first-new", "new");
+        let dst_dir = temp_dir_with("swap-first-dst", "");
+        let backup_dir = dst_dir.with_file_name(format!(
+            "igb-file-test-{}-swap-first-backup",
+            std::process::id()
+        ));
+        fs::remove_dir_all(&dst_dir).unwrap();
+
+        try_swap_dir(&new_dir, &dst_dir, &backup_dir).unwrap();
+        assert_eq!(dir_content(&dst_dir), "new");
+        assert!(!backup_dir.exists());
+
+        let _ = fs::remove_dir_all(&dst_dir);
+    }
+
+    #[test]
+    fn swap_dir_restores_backup_on_failure() {
+        let new_dir = temp_dir_with("swap-missing-new", "");
+        let dst_dir = temp_dir_with("swap-failed-dst", "old");
+        let backup_dir = temp_dir_with("swap-failed-backup", "stale");
+        // 新目录不存在，第二次改名失败
+        fs::remove_dir_all(&new_dir).unwrap();
+
+        assert!(try_swap_dir(&new_dir, &dst_dir, &backup_dir).is_err());
+        assert_eq!(dir_content(&dst_dir), "old");
+        assert!(!backup_dir.exists());
+
+        let _ = fs::remove_dir_all(&dst_dir);
+    }
+}
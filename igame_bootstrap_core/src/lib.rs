@@ -0,0 +1,37 @@
+//! IGame引导器的核心逻辑，界面以外的部分都在这里，其他工具也可以直接使用
+//!
+//! - 检测：[`system_info`]检查系统版本和架构，[`depend`]、[`net_framework`]、[`webview2`]检测运行环境
+//! - 规划：[`depend::detect_depends`]检测每个依赖的安装状态，[`plan`]据此生成可序列化的安装计划
+//! - 下载：[`api`]查询资源地址和大小，[`net`]下载文件并通过[`platform::ProgressNotifier`]报告进度
+//! - 安装：[`plan::DependPlan`]按计划下载并安装依赖，[`depend::check_depends_space`]检查磁盘空间，[`process`]启动IGame安装器
+//! - 更新：[`update`]检查、下载、安装引导器自身的更新，更新通道见[`channel`]
+//! - 预览：[`dry_run`]只检测并输出安装计划，不下载也不安装
+//! - 启动信息：[`trailer`]读写exe末尾附加的启动信息，[`pe`]计算其在文件中的位置
+//!
+//! 与系统相关的功能都在[`platform`]中，Windows以外的系统使用可移植的实现，方便在Linux上编译和测试
+
+pub mod api;
+pub mod channel;
+pub mod config;
+pub mod continuation;
+pub mod crypto;
+pub mod depend;
+pub mod disk;
+pub mod dry_run;
+pub mod error;
+pub mod file;
+pub mod net;
+pub mod net_framework;
+pub mod pe;
+pub mod plan;
+pub mod platform;
+pub mod process;
+pub mod registry;
+pub mod static_var;
+pub mod system_info;
+pub mod time;
+pub mod trailer;
+pub mod update;
+pub mod version;
+pub mod webview2;
+pub mod workspace;
@@ -1,14 +1,15 @@
-use native_windows_gui as nwg;
 use std::io::{self, Read, Write};
 use std::sync::{Arc, Mutex};
 
-use crate::library::file::write_temp_file;
+use crate::platform::ProgressNotifier;
+use crate::workspace::write_workspace_file;
 
+/// 下载到工作目录，通过rate_sender通知下载进度
 pub fn download_file(
     url: &str,
     file_name: &str,
     rate: Arc<Mutex<u8>>,
-    rate_sender: nwg::NoticeSender,
+    rate_sender: impl ProgressNotifier,
 ) -> Result<(), String> {
     let agent = ureq::builder()
         .timeout_connect(std::time::Duration::from_secs(10))
@@ -26,7 +27,7 @@ pub fn download_file(
     let resp_reader = resp.into_reader();
     let mut reader = io::BufReader::new(resp_reader);
 
-    let (_, fd) = match write_temp_file(file_name, false) {
+    let (_, fd) = match write_workspace_file(file_name) {
         Ok(v) => v,
         Err(e) => {
             return Err(format!("创建文件失败\n{:?}", e));
@@ -57,7 +58,7 @@ pub fn download_file(
             let mut r = rate.lock().unwrap();
             if *r != download_rate {
                 *r = download_rate;
-                rate_sender.notice();
+                rate_sender.notify();
             }
         }
         read_size = match reader.read(&mut buffer) {
@@ -84,7 +85,7 @@ pub fn download_file_without_notice(url: &str, file_name: &str) -> Result<(), St
     let resp_reader = resp.into_reader();
     let mut reader = io::BufReader::new(resp_reader);
 
-    let (_, fd) = match write_temp_file(file_name, false) {
+    let (_, fd) = match write_workspace_file(file_name) {
         Ok(v) => v,
         Err(e) => {
             return Err(format!("创建文件失败\n{:?}", e));
@@ -0,0 +1,372 @@
+use crate::config::get_config_value;
+use crate::registry::{Registry, RegistryRoot};
+
+/// 参考微软文档“确定安装了哪些.NET Framework版本”
+/// 同一版本在不同系统上的Release值不同，表中按Release从小到大排列
+pub struct NetFrameworkRelease {
+    pub release: u32,
+    pub version: &'static str,
+    pub os: &'static str,
+}
+
+pub const NET_FRAMEWORK_RELEASES: [NetFrameworkRelease; 22] = [
+    NetFrameworkRelease {
+        release: 378389,
+        version: "4.5",
+        os: "所有系统",
+    },
+    NetFrameworkRelease {
+        release: 378675,
+        version: "4.5.1",
+        os: "Win8.1",
+    },
+    NetFrameworkRelease {
+        release: 378758,
+        version: "4.5.1",
+        os: "其他系统",
+    },
+    NetFrameworkRelease {
+        release: 379893,
+        version: "4.5.2",
+        os: "所有系统",
+    },
+    NetFrameworkRelease {
+        release: 393295,
+        version: "4.6",
+        os: "Win10",
+    },
+    NetFrameworkRelease {
+        release: 393297,
+        version: "4.6",
+        os: "其他系统",
+    },
+    NetFrameworkRelease {
+        release: 394254,
+        version: "4.6.1",
+        os: "Win10 1511",
+    },
+    NetFrameworkRelease {
+        release: 394271,
+        version: "4.6.1",
+        os: "其他系统",
+    },
+    NetFrameworkRelease {
+        release: 394802,
+        version: "4.6.2",
+        os: "Win10 1607",
+    },
+    NetFrameworkRelease {
+        release: 394806,
+        version: "4.6.2",
+        os: "其他系统",
+    },
+    NetFrameworkRelease {
+        release: 460798,
+        version: "4.7",
+        os: "Win10 1703",
+    },
+    NetFrameworkRelease {
+        release: 460805,
+        version: "4.7",
+        os: "其他系统",
+    },
+    NetFrameworkRelease {
+        release: 461308,
+        version: "4.7.1",
+        os: "Win10 1709",
+    },
+    NetFrameworkRelease {
+        release: 461310,
+        version: "4.7.1",
+        os: "其他系统",
+    },
+    NetFrameworkRelease {
+        release: 461808,
+        version: "4.7.2",
+        os: "Win10 1803",
+    },
+    NetFrameworkRelease {
+        release: 461814,
+        version: "4.7.2",
+        os: "其他系统",
+    },
+    NetFrameworkRelease {
+        release: 528040,
+        version: "4.8",
+        os: "Win10 1903/1909",
+    },
+    NetFrameworkRelease {
+        release: 528049,
+        version: "4.8",
+        os: "其他系统",
+    },
+    NetFrameworkRelease {
+        release: 528372,
+        version: "4.8",
+        os: "Win10 2004及以后",
+    },
+    NetFrameworkRelease {
+        release: 528449,
+        version: "4.8",
+        os: "Win11",
+    },
+    NetFrameworkRelease {
+        release: 533320,
+        version: "4.8.1",
+        os: "Win11 22H2及以后",
+    },
+    NetFrameworkRelease {
+        release: 533325,
+        version: "4.8.1",
+        os: "其他系统",
+    },
+];
+
+// 没有单独设置时要求的版本
+const DEFAULT_REQUIRED_VERSION: &str = "4.8";
+/// 依赖清单中安装包的版本，见plan::get_depend_manifest，要求更高的版本时装完也无法满足
+pub const PACKAGED_VERSION: &str = "4.8";
+const REQUIRED_VERSION_CONFIG_NAME: &str = "NetFrameworkRequiredVersion";
+
+/// 返回不超过release的最高版本，低于4.5时返回None
+/// 新系统上可能出现表中没有的更大Release值，按已知的最高版本处理
+pub fn release_to_version(release: u32) -> Option<&'static str> {
+    let mut version: Option<&'static str> = None;
+    for v in NET_FRAMEWORK_RELEASES.iter() {
+        if v.release > release {
+            break;
+        }
+        version = Some(v.version);
+    }
+    return version;
+}
+
+/// 版本对应的最小Release值，安装了这个版本的任意系统都不会低于它
+pub fn version_to_min_release(version: &str) -> Option<u32> {
+    return NET_FRAMEWORK_RELEASES
+        .iter()
+        .find(|v| v.version == version)
+        .map(|v| v.release);
+}
+
+/// 版本不在表中时视为不满足
+pub fn release_satisfies(release: u32, required_version: &str) -> bool {
+    return match version_to_min_release(required_version) {
+        Some(v) => release >= v,
+        None => false,
+    };
+}
+
+/// 没有安装4.5及以上版本时返回None
+pub fn get_installed_release(registry: &dyn Registry) -> Option<u32> {
+    return registry.get_dword(
+        RegistryRoot::LocalMachine,
+        r"SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full",
+        "Release",
+    );
+}
+
+/// 用于诊断和错误上报，没有安装4.5及以上版本时返回None
+pub fn get_installed_version(registry: &dyn Registry) -> Option<String> {
+    return get_installed_release(registry)
+        .and_then(release_to_version)
+        .map(|v| v.to_string());
+}
+
+/// 表中已知且不高于安装包的版本
+pub fn required_version_is_installable(version: &str) -> bool {
+    return match (
+        version_to_min_release(version),
+        version_to_min_release(PACKAGED_VERSION),
+    ) {
+        (Some(v), Some(p)) => v <= p,
+        _ => false,
+    };
+}
+
+/// 设置中的版本必须能通过安装包满足，否则使用默认值
+pub fn get_required_version() -> String {
+    if let Some(v) = get_config_value(REQUIRED_VERSION_CONFIG_NAME) {
+        if required_version_is_installable(&v) {
+            return v;
+        }
+    }
+    return DEFAULT_REQUIRED_VERSION.to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::FakeRegistry;
+
+    fn installed_version(fixture: &str) -> Option<String> {
+        return get_installed_version(&FakeRegistry::from_fixture(fixture).unwrap());
+    }
+
+    #[test]
+    fn installed_version_from_registry() {
+        let cases: [(&str, Option<&str>); 6] = [
+            ("", None),
+            // 只安装了4.0
+            (
+                r#"[HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full]
+                "Version"="4.0.30319""#,
+                None,
+            ),
+            (
+                r#"[HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full]
+                "Release"=dword:0005c733"#,
+                Some("4.5.1"),
+            ),
+            (
+                r#"[HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full]
+                "Release"=dword:00080ff4"#,
+                Some("4.8"),
+            ),
+            (
+                r#"[HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full]
+                "Release"=dword:00082348"#,
+                Some("4.8.1"),
+            ),
+            // Release不是dword时按未安装处理
+            (
+                r#"[HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full]
+                "Release"="528372""#,
+                None,
+            ),
+        ];
+        for (fixture, version) in cases {
+            assert_eq!(
+                installed_version(fixture).as_deref(),
+                version,
+                "{}",
+                fixture
+            );
+        }
+    }
+
+    #[test]
+    fn release_to_version_table() {
+        let cases: [(u32, Option<&str>); 24] = [
+            (0, None),
+            // 低于4.5
+            (378388, None),
+            (378389, Some("4.5")),
+            (378674, Some("4.5")),
+            // 4.5.1在Win8.1和其他系统上的Release值
+            (378675, Some("4.5.1")),
+            (378758, Some("4.5.1")),
+            (379893, Some("4.5.2")),
+            (393295, Some("4.6")),
+            (393297, Some("4.6")),
+            (394254, Some("4.6.1")),
+            (394806, Some("4.6.2")),
+            (460798, Some("4.7")),
+            (461310, Some("4.7.1")),
+            (461814, Some("4.7.2")),
+            // 4.8在各系统上的Release值
+            (528039, Some("4.7.2")),
+            (528040, Some("4.8")),
+            (528049, Some("4.8")),
+            (528372, Some("4.8")),
+            (528449, Some("4.8")),
+            // 4.8.1在Win11 22H2和其他系统上的Release值
+            (533320, Some("4.8.1")),
+            (533325, Some("4.8.1")),
+            // 比表中所有值都新的Release按已知的最高版本处理
+            (533326, Some("4.8.1")),
+            (999999, Some("4.8.1")),
+            (u32::MAX, Some("4.8.1")),
+        ];
+        for (release, version) in cases {
+            assert_eq!(release_to_version(release), version, "release {}", release);
+        }
+    }
+
+    #[test]
+    fn version_to_min_release_table() {
+        let cases: [(&str, Option<u32>); 12] = [
+            ("4.5", Some(378389)),
+            ("4.5.1", Some(378675)),
+            ("4.5.2", Some(379893)),
+            ("4.6", Some(393295)),
+            ("4.6.2", Some(394802)),
+            ("4.7.2", Some(461808)),
+            ("4.8", Some(528040)),
+            ("4.8.1", Some(533320)),
+            // 表中没有的版本
+            ("4.0", None),
+            ("4.9", None),
+            ("4.8.0", None),
+            ("", None),
+        ];
+        for (version, release) in cases {
+            assert_eq!(
+                version_to_min_release(version),
+                release,
+                "version {}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn required_version_is_installable_table() {
+        let cases: [(&str, bool); 7] = [
+            ("4.5", true),
+            ("4.6.2", true),
+            ("4.7.2", true),
+            ("4.8", true),
+            // 没有4.8.1的安装包
+            ("4.8.1", false),
+            ("4.9", false),
+            ("", false),
+        ];
+        for (version, installable) in cases {
+            assert_eq!(
+                required_version_is_installable(version),
+                installable,
+                "{}",
+                version
+            );
+        }
+        assert!(required_version_is_installable(DEFAULT_REQUIRED_VERSION));
+    }
+
+    #[test]
+    fn release_satisfies_table() {
+        let cases: [(u32, &str, bool); 17] = [
+            // 低于4.5
+            (0, "4.5", false),
+            (378388, "4.5", false),
+            (378389, "4.5", true),
+            // 不同系统上的4.5.1都满足4.5.1
+            (378675, "4.5.1", true),
+            (378758, "4.5.1", true),
+            (378675, "4.5.2", false),
+            // 不同系统上的4.8都满足4.8
+            (528040, "4.8", true),
+            (528049, "4.8", true),
+            (528372, "4.8", true),
+            (528449, "4.8", true),
+            (461814, "4.8", false),
+            (528449, "4.8.1", false),
+            (533320, "4.8.1", true),
+            (533325, "4.8", true),
+            // 更新的Release值满足所有已知版本
+            (999999, "4.8.1", true),
+            // 表中没有的版本不认为满足
+            (999999, "4.9", false),
+            (999999, "", false),
+        ];
+        for (release, required_version, satisfied) in cases {
+            assert_eq!(
+                release_satisfies(release, required_version),
+                satisfied,
+                "release {} required {}",
+                release,
+                required_version
+            );
+        }
+    }
+}
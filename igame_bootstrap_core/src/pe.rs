@@ -0,0 +1,220 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+// 只解析定位证书表所需的字段，参考PE/COFF规范
+const DOS_MAGIC: [u8; 2] = [0x4d, 0x5a];
+const PE_SIGNATURE: [u8; 4] = [0x50, 0x45, 0x00, 0x00];
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+const SECURITY_DIRECTORY_INDEX: u64 = 4;
+/// WIN_CERTIFICATE按8字节对齐
+pub const CERTIFICATE_ALIGNMENT: u64 = 8;
+
+pub struct SecurityDirectory {
+    /// 数据目录项在文件中的偏移，修改证书表大小时写回这里
+    pub entry_offset: u64,
+    /// 证书表的文件偏移（安全目录的VirtualAddress字段就是文件偏移）
+    pub offset: u64,
+    pub size: u32,
+}
+
+impl SecurityDirectory {
+    pub fn end(&self) -> u64 {
+        return self.offset + self.size as u64;
+    }
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> std::io::Result<u16> {
+    let mut buffer = [0u8; 2];
+    reader.read_exact(&mut buffer)?;
+    return Ok(u16::from_le_bytes(buffer));
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> std::io::Result<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    return Ok(u32::from_le_bytes(buffer));
+}
+
+fn read_bytes_at<R: Read + Seek>(reader: &mut R, offset: u64, buffer: &mut [u8]) -> bool {
+    return reader
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| reader.read_exact(buffer))
+        .is_ok();
+}
+
+/// 不是PE文件或没有安全目录时返回None
+pub fn read_security_directory<R: Read + Seek>(
+    reader: &mut R,
+) -> Result<Option<SecurityDirectory>, String> {
+    let mut dos_magic = [0u8; 2];
+    if !read_bytes_at(reader, 0, &mut dos_magic) || dos_magic != DOS_MAGIC {
+        return Ok(None);
+    }
+    let mut e_lfanew = [0u8; 4];
+    if !read_bytes_at(reader, 0x3c, &mut e_lfanew) {
+        return Ok(None);
+    }
+    let pe_offset = u32::from_le_bytes(e_lfanew) as u64;
+    let mut pe_signature = [0u8; 4];
+    if !read_bytes_at(reader, pe_offset, &mut pe_signature) || pe_signature != PE_SIGNATURE {
+        return Ok(None);
+    }
+
+    // COFF头20字节，SizeOfOptionalHeader在第16字节
+    let optional_header_size = reader
+        .seek(SeekFrom::Start(pe_offset + 4 + 16))
+        .and_then(|_| read_u16(reader))
+        .map_err(|e| format!("读取PE头失败\n{:?}", e))? as u64;
+    let optional_header_offset = pe_offset + 4 + 20;
+    let magic = reader
+        .seek(SeekFrom::Start(optional_header_offset))
+        .and_then(|_| read_u16(reader))
+        .map_err(|e| format!("读取PE可选头失败\n{:?}", e))?;
+    let (rva_count_offset, directories_offset) = match magic {
+        PE32_MAGIC => (92, 96),
+        PE32_PLUS_MAGIC => (108, 112),
+        _ => return Err(format!("不支持的PE可选头类型：{:#x}", magic)),
+    };
+
+    let rva_count = reader
+        .seek(SeekFrom::Start(optional_header_offset + rva_count_offset))
+        .and_then(|_| read_u32(reader))
+        .map_err(|e| format!("读取PE数据目录失败\n{:?}", e))? as u64;
+    if rva_count <= SECURITY_DIRECTORY_INDEX {
+        return Ok(None);
+    }
+    let entry_offset = optional_header_offset + directories_offset + SECURITY_DIRECTORY_INDEX * 8;
+    if entry_offset + 8 > optional_header_offset + optional_header_size {
+        return Err("PE数据目录超出可选头范围".to_string());
+    }
+
+    let (offset, size) = reader
+        .seek(SeekFrom::Start(entry_offset))
+        .and_then(|_| Ok((read_u32(reader)?, read_u32(reader)?)))
+        .map_err(|e| format!("读取PE安全目录失败\n{:?}", e))?;
+    if offset == 0 || size == 0 {
+        return Ok(None);
+    }
+
+    return Ok(Some(SecurityDirectory {
+        entry_offset,
+        offset: offset as u64,
+        size,
+    }));
+}
+
+pub fn write_security_directory_size<W: Write + Seek>(
+    writer: &mut W,
+    directory: &SecurityDirectory,
+    size: u32,
+) -> Result<(), String> {
+    return writer
+        .seek(SeekFrom::Start(directory.entry_offset + 4))
+        .and_then(|_| writer.write_all(&size.to_le_bytes()))
+        .map_err(|e| format!("写入PE安全目录失败\n{:?}", e));
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const PE_OFFSET: usize = 0x80;
+    const CODE_END: usize = 0x400;
+
+    // 构造只包含定位证书表所需字段的PE文件，证书表不为空时位于文件末尾
+    pub(crate) fn build_pe(pe32_plus: bool, certificate_table: &[u8]) -> Vec<u8> {
+        let (magic, optional_header_size, directories_offset) = if pe32_plus {
+            (PE32_PLUS_MAGIC, 240, 112)
+        } else {
+            (PE32_MAGIC, 224, 96)
+        };
+        let optional_header_offset = PE_OFFSET + 4 + 20;
+        let mut data = vec![0u8; optional_header_offset + optional_header_size];
+        data[..2].copy_from_slice(&DOS_MAGIC);
+        data[0x3c..0x40].copy_from_slice(&(PE_OFFSET as u32).to_le_bytes());
+        data[PE_OFFSET..PE_OFFSET + 4].copy_from_slice(&PE_SIGNATURE);
+        data[PE_OFFSET + 20..PE_OFFSET + 22]
+            .copy_from_slice(&(optional_header_size as u16).to_le_bytes());
+        data[optional_header_offset..optional_header_offset + 2]
+            .copy_from_slice(&magic.to_le_bytes());
+        let rva_count_offset = optional_header_offset + directories_offset - 4;
+        data[rva_count_offset..rva_count_offset + 4].copy_from_slice(&16u32.to_le_bytes());
+        data.resize(CODE_END, 0xcc);
+
+        if !certificate_table.is_empty() {
+            let entry_offset = optional_header_offset + directories_offset + 4 * 8;
+            data[entry_offset..entry_offset + 4].copy_from_slice(&(CODE_END as u32).to_le_bytes());
+            data[entry_offset + 4..entry_offset + 8]
+                .copy_from_slice(&(certificate_table.len() as u32).to_le_bytes());
+            data.extend_from_slice(certificate_table);
+        }
+        return data;
+    }
+
+    // WIN_CERTIFICATE：dwLength u32 | wRevision u16 | wCertificateType u16 | bCertificate
+    pub(crate) fn build_certificate(length: usize) -> Vec<u8> {
+        let mut data = (length as u32).to_le_bytes().to_vec();
+        data.extend_from_slice(&0x0200u16.to_le_bytes());
+        data.extend_from_slice(&0x0002u16.to_le_bytes());
+        data.extend((8..length).map(|i| (i % 251) as u8));
+        return data;
+    }
+
+    #[test]
+    fn reads_security_directory_of_pe32_and_pe32_plus() {
+        for pe32_plus in [false, true] {
+            let data = build_pe(pe32_plus, &build_certificate(533));
+            let directory = read_security_directory(&mut Cursor::new(&data))
+                .unwrap()
+                .unwrap();
+            assert_eq!(directory.offset, CODE_END as u64);
+            assert_eq!(directory.size, 533);
+            assert_eq!(directory.end(), data.len() as u64);
+        }
+    }
+
+    #[test]
+    fn unsigned_pe_has_no_security_directory() {
+        for pe32_plus in [false, true] {
+            let data = build_pe(pe32_plus, &[]);
+            assert!(read_security_directory(&mut Cursor::new(&data))
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn plain_files_are_not_pe() {
+        let mut truncated = build_pe(false, &[]);
+        truncated.truncate(PE_OFFSET + 2);
+        for data in [
+            vec![],
+            b"MZ".to_vec(),
+            vec![0u8; 4096],
+            b"not a pe file".repeat(100),
+            truncated,
+        ] {
+            assert!(read_security_directory(&mut Cursor::new(&data))
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_optional_header() {
+        let mut data = build_pe(false, &build_certificate(64));
+        data[PE_OFFSET + 24] = 0x07;
+        assert!(read_security_directory(&mut Cursor::new(&data)).is_err());
+    }
+
+    #[test]
+    fn writes_security_directory_size() {
+        let mut cursor = Cursor::new(build_pe(true, &build_certificate(64)));
+        let directory = read_security_directory(&mut cursor).unwrap().unwrap();
+        write_security_directory_size(&mut cursor, &directory, 128).unwrap();
+        let directory = read_security_directory(&mut cursor).unwrap().unwrap();
+        assert_eq!(directory.offset, CODE_END as u64);
+        assert_eq!(directory.size, 128);
+    }
+}
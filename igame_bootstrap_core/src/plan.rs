@@ -0,0 +1,762 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::api::{try_get_download_url, try_get_resource_size, ProviderGroup, ResourceSize};
+use crate::depend::{
+    get_depend_status, replace_igame_installer, run_installer, DependDetection,
+    IGAME_INSTALLER_NAME,
+};
+use crate::error::process_error;
+use crate::file::{extract_tzst, try_remove_path};
+use crate::net::download_file;
+use crate::platform::{system_registry, ProgressNotifier};
+use crate::workspace::{get_random_workspace_dir_path, get_workspace_file_path};
+
+// 安装计划只由检测结果、依赖清单和策略计算得出，计算过程不访问系统和网络
+// 下载地址和大小由resolve_install_plan通过接口补全，界面按计划执行，--dry-run输出同一个计划
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractTarget {
+    /// 解压到工作目录下的临时目录，运行安装程序后删除
+    Workspace,
+    /// 先解压到暂存目录，校验通过后整体替换IGame安装器目录
+    #[serde(rename = "igame_installer_dir")]
+    IGameInstallerDir,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum InstallStep {
+    Download {
+        resource_id: i32,
+        file_name: String,
+        // 未补全时为None
+        url: Option<String>,
+        // 获取大小失败时同样为None
+        size: Option<u64>,
+    },
+    /// 比较下载文件的大小
+    Verify {
+        file_name: String,
+        size: Option<u64>,
+    },
+    Extract {
+        file_name: String,
+        target: ExtractTarget,
+        // 解压后的大小
+        size: Option<u64>,
+    },
+    /// 运行上一步解压出的安装程序
+    Run { exe_name: String, args: Vec<String> },
+    /// 重新检测依赖是否满足要求
+    PostVerify { name: String },
+}
+
+impl InstallStep {
+    /// 下载和校验在下载线程中执行，其余步骤在安装线程中执行
+    pub fn is_download(&self) -> bool {
+        return matches!(
+            self,
+            InstallStep::Download { .. } | InstallStep::Verify { .. }
+        );
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct PlannedStep {
+    #[serde(flatten)]
+    pub step: InstallStep,
+    pub reason: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct DependPlan {
+    pub name: String,
+    pub installed_version: Option<String>,
+    pub required_version: Option<String>,
+    pub reason: String,
+    pub steps: Vec<PlannedStep>,
+}
+
+impl DependPlan {
+    /// 已安装但版本过低时说明需要升级，用于安装前的提示
+    pub fn describe(&self) -> String {
+        match (&self.installed_version, &self.required_version) {
+            (Some(installed), Some(required)) => {
+                return format!(
+                    "{}（已安装{}，需要{}，将升级）",
+                    self.name, installed, required
+                );
+            }
+            (Some(installed), None) => {
+                return format!("{}（已安装{}，将升级）", self.name, installed);
+            }
+            _ => return self.name.clone(),
+        }
+    }
+
+    /// 执行所有下载和校验步骤
+    pub fn download(
+        &self,
+        rate: Arc<Mutex<u8>>,
+        rate_sender: impl ProgressNotifier + Copy,
+    ) -> Result<(), String> {
+        for planned in self.steps.iter().filter(|v| v.step.is_download()) {
+            execute_download_step(&planned.step, rate.clone(), rate_sender)?;
+        }
+        return Ok(());
+    }
+
+    /// 按顺序执行解压、运行安装程序和重新检测
+    pub fn install(&self) -> Result<(), String> {
+        let mut extracted_dir: Option<PathBuf> = None;
+        for planned in self.steps.iter().filter(|v| !v.step.is_download()) {
+            execute_install_step(&planned.step, &mut extracted_dir)?;
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct InstallPlan {
+    pub os_arch: u8,
+    pub provider_group: ProviderGroup,
+    /// 按安装顺序排列，只包含需要安装的依赖
+    pub depends: Vec<DependPlan>,
+}
+
+impl InstallPlan {
+    pub fn is_empty(&self) -> bool {
+        return self.depends.is_empty();
+    }
+
+    /// 只需要安装IGame安装器时不提示用户
+    pub fn needs_prompt(&self) -> bool {
+        return self.depends.iter().any(|v| v.name != IGAME_INSTALLER_NAME);
+    }
+
+    /// 工作目录中需要的空间：下载的文件和解压到工作目录的文件
+    pub fn workspace_size(&self) -> u64 {
+        let mut size: u64 = 0;
+        for planned in self.depends.iter().flat_map(|v| v.steps.iter()) {
+            match &planned.step {
+                InstallStep::Download { size: Some(v), .. } => size += v,
+                InstallStep::Extract {
+                    target: ExtractTarget::Workspace,
+                    size: Some(v),
+                    ..
+                } => size += v,
+                _ => {}
+            }
+        }
+        return size;
+    }
+
+    /// 所有下载步骤都已获取大小，否则无法检查磁盘空间
+    pub fn sizes_resolved(&self) -> bool {
+        return self
+            .depends
+            .iter()
+            .flat_map(|v| v.steps.iter())
+            .all(|v| !matches!(v.step, InstallStep::Download { size: None, .. }));
+    }
+
+    /// IGame安装器目录中需要的空间
+    pub fn install_dir_size(&self) -> u64 {
+        let mut size: u64 = 0;
+        for planned in self.depends.iter().flat_map(|v| v.steps.iter()) {
+            match &planned.step {
+                InstallStep::Extract {
+                    target: ExtractTarget::IGameInstallerDir,
+                    size: Some(v),
+                    ..
+                } => size += v,
+                _ => {}
+            }
+        }
+        return size;
+    }
+}
+
+/// 生成安装计划时的选项
+pub struct InstallPolicy {
+    pub provider_group: ProviderGroup,
+}
+
+impl Default for InstallPolicy {
+    fn default() -> Self {
+        return InstallPolicy {
+            provider_group: ProviderGroup::Fast,
+        };
+    }
+}
+
+enum PackageInstall {
+    Run {
+        exe_name: &'static str,
+        args: &'static [&'static str],
+    },
+    ReplaceIGameInstaller,
+}
+
+// 依赖清单中的一个安装包
+struct PackageManifest {
+    description: &'static str,
+    resource_id: i32,
+    file_name: &'static str,
+    install: PackageInstall,
+}
+
+fn get_depend_manifest(name: &str, os_arch: u8) -> Result<Vec<PackageManifest>, String> {
+    match name {
+        ".NET框架 4.8" => {
+            return Ok(vec![
+                PackageManifest {
+                    description: "证书修复工具",
+                    resource_id: 13,
+                    file_name: "Rootsupd.tzst",
+                    install: PackageInstall::Run {
+                        exe_name: "Rootsupd.exe",
+                        args: &[],
+                    },
+                },
+                PackageManifest {
+                    description: ".NET框架 4.8",
+                    resource_id: 9,
+                    file_name: ".NET Framework 4.8.tzst",
+                    install: PackageInstall::Run {
+                        exe_name: ".NET Framework 4.8.exe",
+                        args: &["/passive", "/showrmui", "/promptrestart"],
+                    },
+                },
+            ]);
+        }
+        "WebView2" => {
+            if os_arch == 64 {
+                return Ok(vec![PackageManifest {
+                    description: "WebView2",
+                    resource_id: 11,
+                    file_name: "WebView2Installer.tzst",
+                    install: PackageInstall::Run {
+                        exe_name: "WebView2RuntimeInstallerX64.exe",
+                        args: &["/silent", "/install"],
+                    },
+                }]);
+            } else {
+                return Ok(vec![PackageManifest {
+                    description: "WebView2",
+                    resource_id: 10,
+                    file_name: "WebView2Installer.tzst",
+                    install: PackageInstall::Run {
+                        exe_name: "WebView2RuntimeInstallerX32.exe",
+                        args: &["/silent", "/install"],
+                    },
+                }]);
+            }
+        }
+        IGAME_INSTALLER_NAME => {
+            return Ok(vec![PackageManifest {
+                description: IGAME_INSTALLER_NAME,
+                resource_id: 12,
+                file_name: "IGameInstaller.tzst",
+                install: PackageInstall::ReplaceIGameInstaller,
+            }]);
+        }
+        _ => return Err(format!("依赖名称不正确：{}", name)),
+    }
+}
+
+fn describe_reason(detection: &DependDetection) -> String {
+    match (
+        &detection.status.installed_version,
+        &detection.status.required_version,
+    ) {
+        (Some(installed), Some(required)) => {
+            return format!("已安装{}，低于需要的{}", installed, required)
+        }
+        (Some(installed), None) => return format!("已安装{}，需要更新", installed),
+        _ => return "未安装".to_string(),
+    }
+}
+
+fn plan_depend(detection: &DependDetection, os_arch: u8) -> Result<DependPlan, String> {
+    let mut steps: Vec<PlannedStep> = Vec::new();
+    let packages = get_depend_manifest(&detection.name, os_arch)?;
+    for package in packages.iter() {
+        steps.push(PlannedStep {
+            step: InstallStep::Download {
+                resource_id: package.resource_id,
+                file_name: package.file_name.to_string(),
+                url: None,
+                size: None,
+            },
+            reason: format!("下载{}的安装包", package.description),
+        });
+        steps.push(PlannedStep {
+            step: InstallStep::Verify {
+                file_name: package.file_name.to_string(),
+                size: None,
+            },
+            reason: "确认安装包下载完整".to_string(),
+        });
+    }
+    for package in packages.iter() {
+        match &package.install {
+            PackageInstall::Run { exe_name, args } => {
+                steps.push(PlannedStep {
+                    step: InstallStep::Extract {
+                        file_name: package.file_name.to_string(),
+                        target: ExtractTarget::Workspace,
+                        size: None,
+                    },
+                    reason: "解压到工作目录".to_string(),
+                });
+                steps.push(PlannedStep {
+                    step: InstallStep::Run {
+                        exe_name: exe_name.to_string(),
+                        args: args.iter().map(|v| v.to_string()).collect(),
+                    },
+                    reason: format!("安装{}", package.description),
+                });
+            }
+            PackageInstall::ReplaceIGameInstaller => {
+                steps.push(PlannedStep {
+                    step: InstallStep::Extract {
+                        file_name: package.file_name.to_string(),
+                        target: ExtractTarget::IGameInstallerDir,
+                        size: None,
+                    },
+                    reason: "校验后替换安装目录，失败时还原旧版本".to_string(),
+                });
+            }
+        }
+    }
+    steps.push(PlannedStep {
+        step: InstallStep::PostVerify {
+            name: detection.name.clone(),
+        },
+        reason: "确认安装后满足要求".to_string(),
+    });
+
+    return Ok(DependPlan {
+        name: detection.name.clone(),
+        installed_version: detection.status.installed_version.clone(),
+        required_version: detection.status.required_version.clone(),
+        reason: describe_reason(detection),
+        steps,
+    });
+}
+
+/// 为不满足要求的依赖生成安装步骤，下载地址和大小由resolve_install_plan补全
+pub fn compute_install_plan(
+    detections: &[DependDetection],
+    os_arch: u8,
+    policy: &InstallPolicy,
+) -> Result<InstallPlan, String> {
+    let mut depends: Vec<DependPlan> = Vec::new();
+    for detection in detections.iter().filter(|v| !v.status.satisfied) {
+        depends.push(plan_depend(detection, os_arch)?);
+    }
+    return Ok(InstallPlan {
+        os_arch,
+        provider_group: policy.provider_group,
+        depends,
+    });
+}
+
+/// 通过接口补全下载地址和大小，获取下载地址失败时退出
+/// 大小只用于检查磁盘空间和下载是否完整，获取失败时记录日志并跳过这些检查
+pub fn resolve_install_plan(plan: &mut InstallPlan) {
+    let result = fill_install_plan(plan, |resource_id, provider_group| {
+        let download_url = try_get_download_url(resource_id, provider_group)?;
+        let resource_size = match try_get_resource_size(resource_id) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                process_error(
+                    format!("获取资源{}的大小失败，跳过空间检查：{}", resource_id, e),
+                    false,
+                    true,
+                    false,
+                    false,
+                );
+                None
+            }
+        };
+        return Ok((download_url, resource_size));
+    });
+    if let Err(e) = result {
+        process_error(e, true, true, true, true);
+    }
+}
+
+/// 请求失败时返回错误，不弹窗、不上报也不退出，供--dry-run使用
+/// 与正常运行一致，获取大小失败时大小为None
+pub fn try_resolve_install_plan(plan: &mut InstallPlan) -> Result<(), String> {
+    return fill_install_plan(plan, |resource_id, provider_group| {
+        return Ok((
+            try_get_download_url(resource_id, provider_group)?,
+            try_get_resource_size(resource_id).ok(),
+        ));
+    });
+}
+
+fn fill_install_plan(
+    plan: &mut InstallPlan,
+    fetch: impl Fn(i32, &ProviderGroup) -> Result<(String, Option<ResourceSize>), String>,
+) -> Result<(), String> {
+    let provider_group = plan.provider_group;
+    for depend in plan.depends.iter_mut() {
+        let mut sizes: HashMap<String, ResourceSize> = HashMap::new();
+        for planned in depend.steps.iter_mut() {
+            if let InstallStep::Download {
+                resource_id,
+                file_name,
+                url,
+                size,
+            } = &mut planned.step
+            {
+                let (download_url, resource_size) = fetch(*resource_id, &provider_group)?;
+                *url = Some(download_url);
+                *size = resource_size.as_ref().map(|v| v.download_size);
+                if let Some(v) = resource_size {
+                    sizes.insert(file_name.clone(), v);
+                }
+            }
+        }
+        for planned in depend.steps.iter_mut() {
+            match &mut planned.step {
+                InstallStep::Verify { file_name, size } => {
+                    *size = sizes.get(file_name.as_str()).map(|v| v.download_size);
+                }
+                InstallStep::Extract {
+                    file_name, size, ..
+                } => {
+                    *size = sizes.get(file_name.as_str()).map(|v| v.unpacked_size);
+                }
+                _ => {}
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn execute_download_step(
+    step: &InstallStep,
+    rate: Arc<Mutex<u8>>,
+    rate_sender: impl ProgressNotifier,
+) -> Result<(), String> {
+    match step {
+        InstallStep::Download { file_name, url, .. } => {
+            let url = match url {
+                Some(v) => v,
+                None => return Err(format!("未获取{}的下载地址", file_name)),
+            };
+            {
+                *rate.lock().unwrap() = 0;
+            }
+            rate_sender.notify();
+            return download_file(url.as_str(), file_name, rate, rate_sender);
+        }
+        InstallStep::Verify { file_name, size } => {
+            let file_path = get_workspace_file_path(file_name);
+            let file_size = match std::fs::metadata(&file_path) {
+                Ok(v) => v.len(),
+                Err(e) => return Err(format!("读取下载文件失败：{:?}\n{:?}", file_path, e)),
+            };
+            if let Some(v) = size {
+                if *v != 0 && *v != file_size {
+                    return Err(format!(
+                        "下载文件不完整：{}，应为{}字节，实际为{}字节",
+                        file_name, v, file_size
+                    ));
+                }
+            }
+            return Ok(());
+        }
+        _ => return Err("不是下载步骤".to_string()),
+    }
+}
+
+fn execute_install_step(
+    step: &InstallStep,
+    extracted_dir: &mut Option<PathBuf>,
+) -> Result<(), String> {
+    match step {
+        InstallStep::Extract {
+            file_name,
+            target: ExtractTarget::Workspace,
+            ..
+        } => {
+            let tzst_path = get_workspace_file_path(file_name);
+            let dst_dir = get_random_workspace_dir_path();
+            extract_tzst(&tzst_path, &dst_dir)?;
+            try_remove_path(&tzst_path)?;
+            *extracted_dir = Some(dst_dir);
+        }
+        InstallStep::Extract {
+            file_name,
+            target: ExtractTarget::IGameInstallerDir,
+            ..
+        } => {
+            replace_igame_installer(&get_workspace_file_path(file_name))?;
+        }
+        InstallStep::Run { exe_name, args } => {
+            let dir_path = match extracted_dir.take() {
+                Some(v) => v,
+                None => return Err(format!("运行{}之前没有解压安装包", exe_name)),
+            };
+            run_installer(&dir_path, exe_name, args)?;
+            try_remove_path(&dir_path)?;
+        }
+        InstallStep::PostVerify { name } => {
+            if !get_depend_status(name, system_registry(), false).satisfied {
+                return Err(format!("{}安装完成后仍不满足要求", name));
+            }
+        }
+        _ => return Err("不是安装步骤".to_string()),
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::depend::{DependStatus, DEPEND_NAMES};
+
+    fn detection(name: &str, satisfied: bool) -> DependDetection {
+        return DependDetection {
+            name: name.to_string(),
+            status: DependStatus {
+                installed_version: None,
+                required_version: None,
+                satisfied,
+            },
+        };
+    }
+
+    fn plan_for(names: &[&str], os_arch: u8) -> InstallPlan {
+        let detections: Vec<DependDetection> = names.iter().map(|v| detection(v, false)).collect();
+        return compute_install_plan(&detections, os_arch, &InstallPolicy::default()).unwrap();
+    }
+
+    // 用简短的文本描述步骤，便于按表比较
+    fn describe_step(step: &InstallStep) -> String {
+        match step {
+            InstallStep::Download { resource_id, .. } => {
+                return format!("download {}", resource_id)
+            }
+            InstallStep::Verify { file_name, .. } => return format!("verify {}", file_name),
+            InstallStep::Extract {
+                file_name, target, ..
+            } => return format!("extract {} {:?}", file_name, target),
+            InstallStep::Run { exe_name, .. } => return format!("run {}", exe_name),
+            InstallStep::PostVerify { name } => return format!("post_verify {}", name),
+        }
+    }
+
+    fn describe_steps(plan: &DependPlan) -> Vec<String> {
+        return plan.steps.iter().map(|v| describe_step(&v.step)).collect();
+    }
+
+    #[test]
+    fn steps_per_depend_and_arch() {
+        let cases: [(&str, u8, &[&str]); 5] = [
+            (
+                DEPEND_NAMES[0],
+                64,
+                &[
+                    "download 13",
+                    "verify Rootsupd.tzst",
+                    "download 9",
+                    "verify .NET Framework 4.8.tzst",
+                    "extract Rootsupd.tzst Workspace",
+                    "run Rootsupd.exe",
+                    "extract .NET Framework 4.8.tzst Workspace",
+                    "run .NET Framework 4.8.exe",
+                    "post_verify .NET框架 4.8",
+                ],
+            ),
+            (
+                DEPEND_NAMES[1],
+                64,
+                &[
+                    "download 11",
+                    "verify WebView2Installer.tzst",
+                    "extract WebView2Installer.tzst Workspace",
+                    "run WebView2RuntimeInstallerX64.exe",
+                    "post_verify WebView2",
+                ],
+            ),
+            (
+                DEPEND_NAMES[1],
+                32,
+                &[
+                    "download 10",
+                    "verify WebView2Installer.tzst",
+                    "extract WebView2Installer.tzst Workspace",
+                    "run WebView2RuntimeInstallerX32.exe",
+                    "post_verify WebView2",
+                ],
+            ),
+            (
+                DEPEND_NAMES[2],
+                64,
+                &[
+                    "download 12",
+                    "verify IGameInstaller.tzst",
+                    "extract IGameInstaller.tzst IGameInstallerDir",
+                    "post_verify IGame安装器",
+                ],
+            ),
+            (
+                DEPEND_NAMES[2],
+                32,
+                &[
+                    "download 12",
+                    "verify IGameInstaller.tzst",
+                    "extract IGameInstaller.tzst IGameInstallerDir",
+                    "post_verify IGame安装器",
+                ],
+            ),
+        ];
+        for (name, os_arch, expected) in cases {
+            let plan = plan_for(&[name], os_arch);
+            assert_eq!(plan.os_arch, os_arch);
+            assert_eq!(plan.depends.len(), 1);
+            assert_eq!(
+                describe_steps(&plan.depends[0]),
+                expected,
+                "{} {}",
+                name,
+                os_arch
+            );
+            // 下载和校验都在安装步骤之前
+            let first_install = plan.depends[0]
+                .steps
+                .iter()
+                .position(|v| !v.step.is_download())
+                .unwrap();
+            assert!(plan.depends[0].steps[first_install..]
+                .iter()
+                .all(|v| !v.step.is_download()));
+        }
+    }
+
+    #[test]
+    fn only_unsatisfied_depends_are_planned() {
+        let detections = vec![
+            detection(DEPEND_NAMES[0], true),
+            detection(DEPEND_NAMES[1], false),
+            detection(DEPEND_NAMES[2], false),
+        ];
+        let plan = compute_install_plan(&detections, 64, &InstallPolicy::default()).unwrap();
+        let names: Vec<&str> = plan.depends.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, [DEPEND_NAMES[1], DEPEND_NAMES[2]]);
+        assert!(plan.needs_prompt());
+
+        let plan = plan_for(&[DEPEND_NAMES[2]], 64);
+        assert!(!plan.needs_prompt());
+        assert!(plan_for(&[], 64).is_empty());
+
+        let detections = vec![detection("未知依赖", false)];
+        assert!(compute_install_plan(&detections, 64, &InstallPolicy::default()).is_err());
+    }
+
+    fn stub_size(resource_id: i32) -> ResourceSize {
+        return ResourceSize {
+            download_size: resource_id as u64 * 100,
+            unpacked_size: resource_id as u64 * 1000,
+        };
+    }
+
+    #[test]
+    fn fill_install_plan_sets_urls_and_sizes() {
+        let mut plan = plan_for(&DEPEND_NAMES, 64);
+        assert!(!plan.sizes_resolved());
+        fill_install_plan(&mut plan, |resource_id, provider_group| {
+            return Ok((
+                format!("https://example.com/{}/{}", resource_id, provider_group),
+                Some(stub_size(resource_id)),
+            ));
+        })
+        .unwrap();
+        assert!(plan.sizes_resolved());
+
+        let steps = &plan.depends[0].steps;
+        assert_eq!(
+            steps[0].step,
+            InstallStep::Download {
+                resource_id: 13,
+                file_name: "Rootsupd.tzst".to_string(),
+                url: Some(format!("https://example.com/13/{}", ProviderGroup::Fast)),
+                size: Some(1300),
+            }
+        );
+        assert_eq!(
+            steps[3].step,
+            InstallStep::Verify {
+                file_name: ".NET Framework 4.8.tzst".to_string(),
+                size: Some(900),
+            }
+        );
+        assert_eq!(
+            steps[6].step,
+            InstallStep::Extract {
+                file_name: ".NET Framework 4.8.tzst".to_string(),
+                target: ExtractTarget::Workspace,
+                size: Some(9000),
+            }
+        );
+
+        // 下载13、9、11、12，解压到工作目录13、9、11，解压到安装目录12
+        assert_eq!(
+            plan.workspace_size(),
+            (13 + 9 + 11 + 12) * 100 + (13 + 9 + 11) * 1000
+        );
+        assert_eq!(plan.install_dir_size(), 12 * 1000);
+    }
+
+    #[test]
+    fn fill_install_plan_without_sizes() {
+        let mut plan = plan_for(&[DEPEND_NAMES[1]], 32);
+        fill_install_plan(&mut plan, |resource_id, _| {
+            return Ok((resource_id.to_string(), None));
+        })
+        .unwrap();
+        assert!(!plan.sizes_resolved());
+        assert_eq!(plan.workspace_size(), 0);
+        assert!(plan.depends[0].steps.iter().all(|v| match &v.step {
+            InstallStep::Download { url, size, .. } =>
+                url.as_deref() == Some("10") && size.is_none(),
+            InstallStep::Verify { size, .. } | InstallStep::Extract { size, .. } => size.is_none(),
+            _ => true,
+        }));
+
+        let mut plan = plan_for(&[DEPEND_NAMES[1]], 32);
+        let result = fill_install_plan(&mut plan, |_, _| {
+            return Err("从服务器获取响应失败".to_string());
+        });
+        assert_eq!(result, Err("从服务器获取响应失败".to_string()));
+    }
+
+    #[test]
+    fn plan_serde_round_trip() {
+        let mut plan = plan_for(&DEPEND_NAMES, 64);
+        fill_install_plan(&mut plan, |resource_id, _| {
+            return Ok((resource_id.to_string(), Some(stub_size(resource_id))));
+        })
+        .unwrap();
+        let json = serde_json::to_string(&plan).unwrap();
+        assert_eq!(serde_json::from_str::<InstallPlan>(&json).unwrap(), plan);
+
+        // 步骤按action区分，原因与步骤在同一层
+        let value = serde_json::to_value(&plan).unwrap();
+        let step = &value["depends"][2]["steps"][2];
+        assert_eq!(step["action"], "extract");
+        assert_eq!(step["target"], "igame_installer_dir");
+        assert_eq!(step["reason"], "校验后替换安装目录，失败时还原旧版本");
+    }
+}
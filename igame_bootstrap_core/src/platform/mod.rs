@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+// 与系统相关的功能都通过这里的接口调用
+// Windows上使用系统API实现，其他系统上使用可移植的实现，只用于编译和测试下载、解压、更新和依赖检测的逻辑
+#[cfg(not(windows))]
+mod portable;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(not(windows))]
+pub use self::portable::{attach_console, system_registry, SystemPlatform};
+#[cfg(windows)]
+pub use self::windows::{attach_console, system_registry, SystemPlatform, WinRegistry};
+
+/// 启动其他程序
+pub trait Launcher {
+    /// 用户取消UAC提示时不算失败
+    fn start_as_admin(&self, exe_path: &str, dir_path: &str, args: &str) -> Result<(), String>;
+}
+
+pub trait MessageBox {
+    fn show_error(&self, title: &str, message: &str) -> Result<(), String>;
+}
+
+pub trait SpecialFolders {
+    fn temp_dir(&self) -> PathBuf;
+
+    /// 64位系统上始终返回64位程序的目录
+    fn program_files_dir(&self) -> PathBuf;
+}
+
+pub trait Volumes {
+    fn get_free_space(&self, path: &PathBuf) -> Result<u64, String>;
+
+    fn list_fixed_drive_roots(&self) -> Vec<PathBuf>;
+}
+
+pub trait FileVersion {
+    /// 读取exe的版本资源，没有时返回None
+    fn get_file_version(&self, path: &PathBuf) -> Option<String>;
+}
+
+/// 下载进度变化时通知界面刷新
+pub trait ProgressNotifier {
+    fn notify(&self);
+}
+
+/// 不需要显示进度时使用
+#[derive(Clone, Copy, Default)]
+pub struct NoopNotifier;
+
+impl ProgressNotifier for NoopNotifier {
+    fn notify(&self) {}
+}
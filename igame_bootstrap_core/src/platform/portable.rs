@@ -0,0 +1,82 @@
+use lazy_static::lazy_static;
+use std::path::PathBuf;
+
+use crate::platform::{FileVersion, Launcher, MessageBox, SpecialFolders, Volumes};
+use crate::registry::{FakeRegistry, Registry};
+
+lazy_static! {
+    // 没有系统注册表，读写的配置只保存在内存中
+    static ref PORTABLE_REGISTRY: FakeRegistry = Default::default();
+}
+
+pub struct SystemPlatform;
+
+impl Launcher for SystemPlatform {
+    /// 没有UAC，直接以当前用户启动
+    fn start_as_admin(&self, exe_path: &str, dir_path: &str, args: &str) -> Result<(), String> {
+        let args: Vec<&str> = args
+            .split('"')
+            .enumerate()
+            .flat_map(|(i, v)| {
+                // 引号内的部分作为一个参数，引号外的按空白分割
+                if i % 2 == 1 {
+                    return vec![v];
+                }
+                return v.split_whitespace().collect();
+            })
+            .collect();
+        match std::process::Command::new(exe_path)
+            .args(args)
+            .current_dir(dir_path)
+            .spawn()
+        {
+            Err(e) => return Err(format!("启动程序失败：{}\n{:?}", exe_path, e)),
+            _ => {}
+        };
+        return Ok(());
+    }
+}
+
+impl MessageBox for SystemPlatform {
+    fn show_error(&self, title: &str, message: &str) -> Result<(), String> {
+        eprintln!("{}\n{}", title, message);
+        return Ok(());
+    }
+}
+
+impl SpecialFolders for SystemPlatform {
+    fn temp_dir(&self) -> PathBuf {
+        return std::env::temp_dir();
+    }
+
+    /// 放在临时目录下，测试时不需要额外的权限
+    fn program_files_dir(&self) -> PathBuf {
+        let mut dir_path = std::env::temp_dir();
+        dir_path.push("Program Files");
+        return dir_path;
+    }
+}
+
+impl Volumes for SystemPlatform {
+    /// 无法获取剩余空间，视为空间充足
+    fn get_free_space(&self, _path: &PathBuf) -> Result<u64, String> {
+        return Ok(u64::MAX);
+    }
+
+    fn list_fixed_drive_roots(&self) -> Vec<PathBuf> {
+        return Vec::new();
+    }
+}
+
+impl FileVersion for SystemPlatform {
+    fn get_file_version(&self, _path: &PathBuf) -> Option<String> {
+        return None;
+    }
+}
+
+/// 本来就在控制台中运行
+pub fn attach_console() {}
+
+pub fn system_registry() -> &'static dyn Registry {
+    return &*PORTABLE_REGISTRY;
+}
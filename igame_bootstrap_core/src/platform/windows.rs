@@ -0,0 +1,261 @@
+use std::ffi::OsStr;
+use std::iter::once;
+use std::os::windows::ffi::OsStrExt;
+use std::path::PathBuf;
+use winreg::enums::{
+    HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_32KEY, KEY_WOW64_64KEY, KEY_WRITE,
+};
+use winreg::RegKey;
+
+use crate::platform::{FileVersion, Launcher, MessageBox, SpecialFolders, Volumes};
+use crate::registry::{Registry, RegistryRoot, RegistryValue, RegistryView};
+
+pub fn windows_ptr(value: &str) -> Vec<u16> {
+    return OsStr::new(value).encode_wide().chain(once(0)).collect();
+}
+
+pub struct SystemPlatform;
+
+// 对应VS_FIXEDFILEINFO，winapi中没有这个结构
+#[allow(dead_code)]
+#[repr(C)]
+struct FixedFileInfo {
+    signature: u32,
+    struc_version: u32,
+    file_version_ms: u32,
+    file_version_ls: u32,
+    product_version_ms: u32,
+    product_version_ls: u32,
+    file_flags_mask: u32,
+    file_flags: u32,
+    file_os: u32,
+    file_type: u32,
+    file_subtype: u32,
+    file_date_ms: u32,
+    file_date_ls: u32,
+}
+
+impl Launcher for SystemPlatform {
+    fn start_as_admin(&self, exe_path: &str, dir_path: &str, args: &str) -> Result<(), String> {
+        let ret = unsafe {
+            winapi::um::shellapi::ShellExecuteW(
+                std::ptr::null_mut(),
+                windows_ptr("runas").as_ptr(),
+                windows_ptr(exe_path).as_ptr(),
+                windows_ptr(args).as_ptr(),
+                windows_ptr(dir_path).as_ptr(),
+                1,
+            )
+        };
+
+        // 返回5表示用户取消了UAC提示
+        if (ret as usize) != 5 && (ret as usize) <= 32 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        return Ok(());
+    }
+}
+
+impl MessageBox for SystemPlatform {
+    fn show_error(&self, title: &str, message: &str) -> Result<(), String> {
+        use winapi::um::winuser::{MessageBoxW, MB_ICONERROR, MB_OK, MB_SETFOREGROUND};
+
+        let ret = unsafe {
+            MessageBoxW(
+                std::ptr::null_mut(),
+                windows_ptr(message).as_ptr(),
+                windows_ptr(title).as_ptr(),
+                MB_OK | MB_ICONERROR | MB_SETFOREGROUND,
+            )
+        };
+
+        if ret == 0 {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        return Ok(());
+    }
+}
+
+impl SpecialFolders for SystemPlatform {
+    fn temp_dir(&self) -> PathBuf {
+        return std::env::temp_dir();
+    }
+
+    fn program_files_dir(&self) -> PathBuf {
+        // 32位程序的ProgramFiles指向Program Files (x86)，优先使用ProgramW6432
+        for name in ["ProgramW6432", "ProgramFiles"] {
+            if let Some(v) = std::env::var_os(name) {
+                if !v.is_empty() {
+                    return PathBuf::from(v);
+                }
+            }
+        }
+        return PathBuf::from(r"C:\Program Files");
+    }
+}
+
+impl Volumes for SystemPlatform {
+    fn get_free_space(&self, path: &PathBuf) -> Result<u64, String> {
+        use winapi::shared::ntdef::ULARGE_INTEGER;
+        use winapi::um::fileapi::GetDiskFreeSpaceExW;
+
+        // 目标目录可能还不存在，向上找到第一个存在的目录
+        let mut query_path = path.clone();
+        while !query_path.exists() {
+            if !query_path.pop() {
+                return Err(format!("无法确定所在磁盘：{:?}", path));
+            }
+        }
+
+        let mut free_bytes: ULARGE_INTEGER = unsafe { std::mem::zeroed() };
+        let ret = unsafe {
+            GetDiskFreeSpaceExW(
+                windows_ptr(query_path.to_string_lossy().as_ref()).as_ptr(),
+                &mut free_bytes,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if ret == 0 {
+            return Err(format!(
+                "获取磁盘剩余空间失败：{:?}\n{}",
+                query_path,
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        return Ok(unsafe { *free_bytes.QuadPart() });
+    }
+
+    fn list_fixed_drive_roots(&self) -> Vec<PathBuf> {
+        use winapi::um::fileapi::{GetDriveTypeW, GetLogicalDrives};
+        use winapi::um::winbase::DRIVE_FIXED;
+
+        let mut roots: Vec<PathBuf> = Vec::new();
+        let drives = unsafe { GetLogicalDrives() };
+        for i in 0..26 {
+            if drives & (1 << i) == 0 {
+                continue;
+            }
+            let root = format!("{}:\\", (b'A' + i as u8) as char);
+            if unsafe { GetDriveTypeW(windows_ptr(root.as_str()).as_ptr()) } == DRIVE_FIXED {
+                roots.push(PathBuf::from(root));
+            }
+        }
+        return roots;
+    }
+}
+
+impl FileVersion for SystemPlatform {
+    fn get_file_version(&self, path: &PathBuf) -> Option<String> {
+        let path = windows_ptr(path.to_str()?);
+        unsafe {
+            let mut handle: u32 = 0;
+            let size = winapi::um::winver::GetFileVersionInfoSizeW(path.as_ptr(), &mut handle);
+            if size == 0 {
+                return None;
+            }
+            let mut buffer: Vec<u8> = vec![0; size as usize];
+            if winapi::um::winver::GetFileVersionInfoW(
+                path.as_ptr(),
+                0,
+                size,
+                buffer.as_mut_ptr() as *mut winapi::ctypes::c_void,
+            ) == 0
+            {
+                return None;
+            }
+            let mut info: *mut winapi::ctypes::c_void = std::ptr::null_mut();
+            let mut info_len: u32 = 0;
+            if winapi::um::winver::VerQueryValueW(
+                buffer.as_ptr() as *const winapi::ctypes::c_void,
+                windows_ptr("\\").as_ptr(),
+                &mut info,
+                &mut info_len,
+            ) == 0
+                || info.is_null()
+                || (info_len as usize) < std::mem::size_of::<FixedFileInfo>()
+            {
+                return None;
+            }
+            let info = &*(info as *const FixedFileInfo);
+            return Some(format!(
+                "{}.{}.{}.{}",
+                info.file_version_ms >> 16,
+                info.file_version_ms & 0xffff,
+                info.file_version_ls >> 16,
+                info.file_version_ls & 0xffff
+            ));
+        }
+    }
+}
+
+/// 读写系统注册表
+pub struct WinRegistry;
+
+fn predef(root: RegistryRoot) -> RegKey {
+    match root {
+        RegistryRoot::LocalMachine => return RegKey::predef(HKEY_LOCAL_MACHINE),
+        RegistryRoot::CurrentUser => return RegKey::predef(HKEY_CURRENT_USER),
+    }
+}
+
+impl Registry for WinRegistry {
+    fn get_value(
+        &self,
+        root: RegistryRoot,
+        view: RegistryView,
+        path: &str,
+        name: &str,
+    ) -> Option<RegistryValue> {
+        let flags = match view {
+            RegistryView::Default => KEY_READ,
+            RegistryView::Wow64_32 => KEY_READ | KEY_WOW64_32KEY,
+            RegistryView::Wow64_64 => KEY_READ | KEY_WOW64_64KEY,
+        };
+        let key = match predef(root).open_subkey_with_flags(path, flags) {
+            Ok(v) => v,
+            Err(_) => {
+                return None;
+            }
+        };
+        if let Ok(v) = key.get_value::<String, _>(name) {
+            return Some(RegistryValue::String(v));
+        }
+        if let Ok(v) = key.get_value::<u32, _>(name) {
+            return Some(RegistryValue::Dword(v));
+        }
+        return None;
+    }
+
+    fn set_string(
+        &self,
+        root: RegistryRoot,
+        path: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<(), String> {
+        let (key, _) = match predef(root).create_subkey_with_flags(path, KEY_WRITE) {
+            Ok(v) => v,
+            Err(e) => return Err(format!("打开注册表失败：{}\n{:?}", path, e)),
+        };
+        match key.set_value(name, &value) {
+            Err(e) => return Err(format!("写入注册表失败：{}\\{}\n{:?}", path, name, e)),
+            _ => {}
+        };
+        return Ok(());
+    }
+}
+
+/// 作为GUI程序运行时没有控制台，从命令行启动时输出到命令行所在的控制台
+pub fn attach_console() {
+    use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+pub fn system_registry() -> &'static dyn Registry {
+    return &WinRegistry;
+}
@@ -0,0 +1,152 @@
+use crate::depend::get_igame_installer_dir_path;
+use crate::error::process_error;
+use crate::platform::{Launcher, SystemPlatform};
+use crate::trailer::LaunchPayload;
+use crate::workspace::try_remove_workspace;
+
+pub fn start_exe_as_admin(exe_path: &str, dir_path: &str, args: &str) {
+    match SystemPlatform.start_as_admin(exe_path, dir_path, args) {
+        Err(e) => process_error(e, true, true, true, true),
+        _ => {}
+    };
+}
+
+// 按CommandLineToArgvW的规则加引号：引号前和结尾的反斜杠需要加倍，否则会转义后面的引号
+fn quote_arg(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut backslashes: usize = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes += 1;
+        } else {
+            if c == '"' {
+                quoted.push_str(&"\\".repeat(backslashes + 1));
+            }
+            backslashes = 0;
+        }
+        quoted.push(c);
+    }
+    quoted.push_str(&"\\".repeat(backslashes));
+    quoted.push('"');
+    return quoted;
+}
+
+pub fn build_args(args: &[String]) -> String {
+    let args: Vec<String> = args.iter().map(|v| quote_arg(v)).collect();
+    return args.join(" ");
+}
+
+fn build_installer_args(payload: &LaunchPayload) -> String {
+    let mut args: Vec<String> = payload
+        .resource_ids
+        .iter()
+        .map(|id| quote_arg(id.to_string().as_str()))
+        .collect();
+    for arg in payload.installer_args.iter() {
+        args.push(quote_arg(arg));
+    }
+    if let Some(v) = &payload.channel {
+        args.push(quote_arg(format!("--channel={}", v).as_str()));
+    }
+    if let Some(v) = &payload.attribution {
+        args.push(quote_arg(format!("--attribution={}", v).as_str()));
+    }
+    return args.join(" ");
+}
+
+pub fn start_igame_installer(payload: &LaunchPayload) {
+    let dir_path = get_igame_installer_dir_path();
+    let mut exe_path = dir_path.clone();
+    exe_path.push("IGameInstaller.exe");
+    let args = build_installer_args(payload);
+
+    start_exe_as_admin(
+        exe_path.to_string_lossy().as_ref(),
+        dir_path.to_string_lossy().as_ref(),
+        args.as_str(),
+    );
+}
+
+pub fn exit(code: i32) {
+    try_remove_workspace();
+    std::process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按CommandLineToArgvW的规则拆分参数，用来检查build_args的结果
+    fn split_args(command_line: &str) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        let mut current: Option<String> = None;
+        let mut in_quotes = false;
+        let mut backslashes: usize = 0;
+        for c in command_line.chars() {
+            match c {
+                '\\' => backslashes += 1,
+                '"' => {
+                    let arg = current.get_or_insert_with(String::new);
+                    arg.push_str(&"\\".repeat(backslashes / 2));
+                    if backslashes % 2 == 1 {
+                        arg.push('"');
+                    } else {
+                        in_quotes = !in_quotes;
+                    }
+                    backslashes = 0;
+                }
+                _ => {
+                    let arg = current.get_or_insert_with(String::new);
+                    arg.push_str(&"\\".repeat(backslashes));
+                    backslashes = 0;
+                    if c == ' ' && !in_quotes {
+                        args.extend(current.take());
+                    } else {
+                        arg.push(c);
+                    }
+                }
+            }
+        }
+        if backslashes != 0 {
+            current
+                .get_or_insert_with(String::new)
+                .push_str(&"\\".repeat(backslashes));
+        }
+        args.extend(current.take());
+        return args;
+    }
+
+    #[test]
+    fn build_args_quotes_each_arg() {
+        let cases: [(&[&str], &str); 6] = [
+            (&["a", "b c"], r#""a" "b c""#),
+            (&[""], r#""""#),
+            (&[r"C:\logs\", "--next"], r#""C:\logs\\" "--next""#),
+            (&[r#"say "hi""#], r#""say \"hi\"""#),
+            (&[r#"a\"b"#], r#""a\\\"b""#),
+            (&[r"\\server\share\a b"], r#""\\server\share\a b""#),
+        ];
+        for (args, expected) in cases {
+            let args: Vec<String> = args.iter().map(|v| v.to_string()).collect();
+            assert_eq!(build_args(&args), expected);
+        }
+    }
+
+    #[test]
+    fn build_args_round_trip() {
+        let args: Vec<String> = [
+            r"--log-dir=C:\logs\",
+            r"C:\Program Files\IGame\",
+            r#"\"#,
+            r#"\\"#,
+            r#"a\\"b"#,
+            r#"""#,
+            "",
+            "--update-channel=beta",
+        ]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+        assert_eq!(split_args(&build_args(&args)), args);
+    }
+}
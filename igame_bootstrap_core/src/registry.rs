@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RegistryRoot {
+    LocalMachine,
+    CurrentUser,
+}
+
+/// 64位系统上HKLM\SOFTWARE分为32位和64位两个视图
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RegistryView {
+    Default,
+    Wow64_32,
+    Wow64_64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RegistryValue {
+    String(String),
+    Dword(u32),
+}
+
+/// 检测逻辑都通过这个接口读取注册表，测试时可以换成内存中的实现
+pub trait Registry {
+    fn get_value(
+        &self,
+        root: RegistryRoot,
+        view: RegistryView,
+        path: &str,
+        name: &str,
+    ) -> Option<RegistryValue>;
+
+    fn set_string(
+        &self,
+        root: RegistryRoot,
+        path: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<(), String>;
+
+    fn get_string(&self, root: RegistryRoot, path: &str, name: &str) -> Option<String> {
+        return self.get_string_in_view(root, RegistryView::Default, path, name);
+    }
+
+    fn get_string_in_view(
+        &self,
+        root: RegistryRoot,
+        view: RegistryView,
+        path: &str,
+        name: &str,
+    ) -> Option<String> {
+        match self.get_value(root, view, path, name)? {
+            RegistryValue::String(v) => return Some(v),
+            RegistryValue::Dword(_) => return None,
+        }
+    }
+
+    fn get_dword(&self, root: RegistryRoot, path: &str, name: &str) -> Option<u32> {
+        match self.get_value(root, RegistryView::Default, path, name)? {
+            RegistryValue::Dword(v) => return Some(v),
+            RegistryValue::String(_) => return None,
+        }
+    }
+}
+
+type FakeRegistryKey = (RegistryRoot, RegistryView, String, String);
+
+/// 内存中的注册表，用于测试检测逻辑，也是非Windows系统上使用的注册表
+/// 指定视图下没有的值会回退到默认视图，与32位系统上的行为一致
+#[derive(Default)]
+pub struct FakeRegistry {
+    values: Mutex<HashMap<FakeRegistryKey, RegistryValue>>,
+}
+
+impl FakeRegistry {
+    fn make_key(root: RegistryRoot, view: RegistryView, path: &str, name: &str) -> FakeRegistryKey {
+        // 注册表的路径和值名称不区分大小写
+        return (
+            root,
+            view,
+            path.trim_matches('\\').to_lowercase(),
+            name.to_lowercase(),
+        );
+    }
+
+    pub fn insert(
+        &self,
+        root: RegistryRoot,
+        view: RegistryView,
+        path: &str,
+        name: &str,
+        value: RegistryValue,
+    ) {
+        self.values
+            .lock()
+            .unwrap()
+            .insert(Self::make_key(root, view, path, name), value);
+    }
+
+    /// 从类似.reg文件的文本读取，例如：
+    ///
+    /// ```text
+    /// [HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full]
+    /// "Release"=dword:00080ff4
+    /// [HKLM32\SOFTWARE\Microsoft\EdgeUpdate\Clients\{...}]
+    /// "pv"="100.0.1185.36"
+    /// ```
+    ///
+    /// 根键可以是HKLM、HKLM32、HKLM64、HKCU，dword为十六进制
+    pub fn from_fixture(fixture: &str) -> Result<FakeRegistry, String> {
+        let registry: FakeRegistry = Default::default();
+        let mut current: Option<(RegistryRoot, RegistryView, String)> = None;
+        for (i, line) in fixture.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                let key = &line[1..line.len() - 1];
+                let (root_name, path) = match key.split_once('\\') {
+                    Some(v) => v,
+                    None => return Err(format!("第{}行注册表路径不正确：{}", i + 1, line)),
+                };
+                let (root, view) = match root_name.to_uppercase().as_str() {
+                    "HKLM" => (RegistryRoot::LocalMachine, RegistryView::Default),
+                    "HKLM32" => (RegistryRoot::LocalMachine, RegistryView::Wow64_32),
+                    "HKLM64" => (RegistryRoot::LocalMachine, RegistryView::Wow64_64),
+                    "HKCU" => (RegistryRoot::CurrentUser, RegistryView::Default),
+                    _ => return Err(format!("第{}行根键不正确：{}", i + 1, root_name)),
+                };
+                current = Some((root, view, path.to_string()));
+                continue;
+            }
+
+            let (root, view, path) = match &current {
+                Some(v) => v,
+                None => return Err(format!("第{}行的值不属于任何注册表项", i + 1)),
+            };
+            let (name, value) = match line.split_once('=') {
+                Some((n, v)) => (n.trim().trim_matches('"'), v.trim()),
+                None => return Err(format!("第{}行格式不正确：{}", i + 1, line)),
+            };
+            let value = if let Some(v) = value.strip_prefix("dword:") {
+                match u32::from_str_radix(v, 16) {
+                    Ok(v) => RegistryValue::Dword(v),
+                    Err(_) => return Err(format!("第{}行dword不正确：{}", i + 1, v)),
+                }
+            } else if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+                RegistryValue::String(value[1..value.len() - 1].to_string())
+            } else {
+                return Err(format!("第{}行值不正确：{}", i + 1, value));
+            };
+            registry.insert(*root, *view, path, name, value);
+        }
+        return Ok(registry);
+    }
+}
+
+impl Registry for FakeRegistry {
+    fn get_value(
+        &self,
+        root: RegistryRoot,
+        view: RegistryView,
+        path: &str,
+        name: &str,
+    ) -> Option<RegistryValue> {
+        let values = self.values.lock().unwrap();
+        if let Some(v) = values.get(&Self::make_key(root, view, path, name)) {
+            return Some(v.clone());
+        }
+        return values
+            .get(&Self::make_key(root, RegistryView::Default, path, name))
+            .cloned();
+    }
+
+    fn set_string(
+        &self,
+        root: RegistryRoot,
+        path: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<(), String> {
+        self.insert(
+            root,
+            RegistryView::Default,
+            path,
+            name,
+            RegistryValue::String(value.to_string()),
+        );
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_fixture_values() {
+        let registry = FakeRegistry::from_fixture(
+            r#"
+            ; 注释和空行会被忽略
+            [HKLM\SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full]
+            "Release"=dword:00080ff4
+            "Version"="4.8.04084"
+
+            [HKLM32\SOFTWARE\Test]
+            "pv"="1.0"
+            [HKCU\SOFTWARE\Test]
+            "pv"="2.0"
+            "#,
+        )
+        .unwrap();
+
+        let path = r"SOFTWARE\Microsoft\NET Framework Setup\NDP\v4\Full";
+        assert_eq!(
+            registry.get_dword(RegistryRoot::LocalMachine, path, "Release"),
+            Some(528372)
+        );
+        assert_eq!(
+            registry.get_string(RegistryRoot::LocalMachine, path, "Version"),
+            Some("4.8.04084".to_string())
+        );
+        // 类型不匹配时读不到
+        assert_eq!(
+            registry.get_string(RegistryRoot::LocalMachine, path, "Release"),
+            None
+        );
+        assert_eq!(
+            registry.get_dword(RegistryRoot::LocalMachine, path, "Version"),
+            None
+        );
+        // 路径和名称不区分大小写
+        assert_eq!(
+            registry.get_dword(
+                RegistryRoot::LocalMachine,
+                r"software\microsoft\net framework setup\ndp\v4\full\",
+                "RELEASE"
+            ),
+            Some(528372)
+        );
+
+        assert_eq!(
+            registry.get_string_in_view(
+                RegistryRoot::LocalMachine,
+                RegistryView::Wow64_32,
+                r"SOFTWARE\Test",
+                "pv"
+            ),
+            Some("1.0".to_string())
+        );
+        assert_eq!(
+            registry.get_string(RegistryRoot::LocalMachine, r"SOFTWARE\Test", "pv"),
+            None
+        );
+        assert_eq!(
+            registry.get_string(RegistryRoot::CurrentUser, r"SOFTWARE\Test", "pv"),
+            Some("2.0".to_string())
+        );
+    }
+
+    #[test]
+    fn view_falls_back_to_default() {
+        let registry = FakeRegistry::from_fixture(
+            r#"
+            [HKLM\SOFTWARE\Test]
+            "pv"="1.0"
+            [HKLM64\SOFTWARE\Test]
+            "pv"="2.0"
+            "#,
+        )
+        .unwrap();
+        let get = |view| {
+            registry.get_string_in_view(RegistryRoot::LocalMachine, view, r"SOFTWARE\Test", "pv")
+        };
+        assert_eq!(get(RegistryView::Default), Some("1.0".to_string()));
+        assert_eq!(get(RegistryView::Wow64_32), Some("1.0".to_string()));
+        assert_eq!(get(RegistryView::Wow64_64), Some("2.0".to_string()));
+    }
+
+    #[test]
+    fn set_string_is_readable() {
+        let registry: FakeRegistry = Default::default();
+        registry
+            .set_string(RegistryRoot::CurrentUser, r"SOFTWARE\Test", "Name", "v")
+            .unwrap();
+        assert_eq!(
+            registry.get_string(RegistryRoot::CurrentUser, r"SOFTWARE\Test", "name"),
+            Some("v".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_fixture() {
+        let cases = [
+            ("\"pv\"=\"1.0\"", "不属于任何注册表项"),
+            ("[HKLM]", "注册表路径不正确"),
+            ("[HKCR\\SOFTWARE\\Test]", "根键不正确"),
+            ("[HKLM\\SOFTWARE\\Test]\n\"pv\"", "格式不正确"),
+            ("[HKLM\\SOFTWARE\\Test]\n\"v\"=dword:xyz", "dword不正确"),
+            ("[HKLM\\SOFTWARE\\Test]\n\"v\"=1.0", "值不正确"),
+        ];
+        for (fixture, error) in cases {
+            match FakeRegistry::from_fixture(fixture) {
+                Ok(_) => panic!("应当解析失败：{}", fixture),
+                Err(e) => assert!(e.contains(error), "{} {}", fixture, e),
+            }
+        }
+    }
+}
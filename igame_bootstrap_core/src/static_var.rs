@@ -0,0 +1,42 @@
+use lazy_static::lazy_static;
+use rand::Rng;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::platform::system_registry;
+use crate::system_info::get_os_arch;
+use crate::trailer::LaunchPayload;
+
+lazy_static! {
+    pub static ref UREQ_AGENT: ureq::Agent = {
+        return ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(30))
+            .max_idle_connections_per_host(10)
+            .build();
+    };
+    pub static ref CURRENT_EXE_PATH: String = {
+        return std::env::current_exe()
+            .unwrap()
+            .into_os_string()
+            .into_string()
+            .unwrap();
+    };
+    pub static ref CURRENT_DIR_PATH: String = {
+        return std::env::current_dir()
+            .unwrap()
+            .into_os_string()
+            .into_string()
+            .unwrap();
+    };
+    /// 启动时从自身末尾读取，供错误上报使用
+    pub static ref LAUNCH_PAYLOAD: RwLock<LaunchPayload> = RwLock::new(Default::default());
+    /// 标记同一次运行写入的日志，更新重启后沿用
+    pub static ref LOG_SESSION: RwLock<String> = RwLock::new(
+        rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(8)
+            .map(char::from)
+            .collect()
+    );
+    pub static ref OS_ARCH: u8 = get_os_arch(system_registry());
+}
@@ -0,0 +1,95 @@
+use crate::registry::{Registry, RegistryRoot};
+
+/// 读取不到系统版本时视为不支持
+pub fn os_is_ok(registry: &dyn Registry) -> bool {
+    let current_version_value = match registry.get_string(
+        RegistryRoot::LocalMachine,
+        r"SOFTWARE\Microsoft\Windows NT\CurrentVersion",
+        "CurrentVersion",
+    ) {
+        Some(v) => v,
+        None => {
+            return false;
+        }
+    };
+
+    // 必须是win7 win8.1 win10 win11
+    if current_version_value.starts_with("6.")
+        && (current_version_value != "6.0" || current_version_value != "6.2")
+    {
+        return true;
+    }
+
+    return false;
+}
+
+/// 读取失败时按32位处理
+pub fn get_os_arch(registry: &dyn Registry) -> u8 {
+    let arch = match registry.get_string(
+        RegistryRoot::LocalMachine,
+        r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment",
+        "PROCESSOR_ARCHITECTURE",
+    ) {
+        Some(v) => v,
+        None => {
+            return 32;
+        }
+    };
+
+    if arch == "x86" {
+        return 32;
+    } else {
+        return 64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::FakeRegistry;
+
+    fn registry_with(path: &str, name: &str, value: &str) -> FakeRegistry {
+        return FakeRegistry::from_fixture(&format!(
+            "[HKLM\\{}]\n\"{}\"=\"{}\"",
+            path, name, value
+        ))
+        .unwrap();
+    }
+
+    #[test]
+    fn os_is_ok_table() {
+        // Win10和Win11的CurrentVersion仍为6.3，6.x目前全部放行
+        let cases = [
+            ("5.1", false),
+            ("6.0", true),
+            ("6.1", true),
+            ("6.2", true),
+            ("6.3", true),
+            ("10.0", false),
+            ("", false),
+        ];
+        for (version, ok) in cases {
+            let registry = registry_with(
+                r"SOFTWARE\Microsoft\Windows NT\CurrentVersion",
+                "CurrentVersion",
+                version,
+            );
+            assert_eq!(os_is_ok(&registry), ok, "{}", version);
+        }
+        assert!(!os_is_ok(&FakeRegistry::default()));
+    }
+
+    #[test]
+    fn get_os_arch_table() {
+        let cases = [("x86", 32), ("AMD64", 64), ("ARM64", 64), ("IA64", 64)];
+        for (arch, bits) in cases {
+            let registry = registry_with(
+                r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment",
+                "PROCESSOR_ARCHITECTURE",
+                arch,
+            );
+            assert_eq!(get_os_arch(&registry), bits, "{}", arch);
+        }
+        assert_eq!(get_os_arch(&FakeRegistry::default()), 32);
+    }
+}
@@ -12,6 +12,10 @@ pub fn utc_str_to_china_str(utc_str: &str) -> String {
     return time.to_offset(china_offset).format(&format2).unwrap();
 }
 
+pub fn unix_timestamp() -> u64 {
+    return time::OffsetDateTime::now_utc().unix_timestamp().max(0) as u64;
+}
+
 pub fn generate_timestamp() -> String {
     let china_offset = time::UtcOffset::from_hms(8, 0, 0).unwrap();
     let format =
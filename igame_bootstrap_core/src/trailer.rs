@@ -0,0 +1,954 @@
+//! 附加在exe末尾的数据布局：`[对齐填充][payload][footer]`
+//!
+//! footer固定20字节（小端）：`payload长度u32 | payload的CRC32 u32 | 格式版本u16 | 填充长度u16 | MAGIC`
+//!
+//! 只有写在证书表内时才有对齐填充，记录长度是为了去掉trailer时能准确恢复证书表原来的大小
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use crate::pe::{
+    read_security_directory, write_security_directory_size, SecurityDirectory,
+    CERTIFICATE_ALIGNMENT,
+};
+
+pub const TRAILER_MAGIC: [u8; 8] = [0x49, 0x47, 0x42, 0x54, 0x52, 0x4c, 0x52, 0x1a];
+pub const TRAILER_FOOTER_SIZE: usize = 20;
+/// 格式版本1：payload为大端i32资源ID
+pub const TRAILER_VERSION_RESOURCE_ID: u16 = 1;
+/// 格式版本2：payload为JSON格式的LaunchPayload
+pub const TRAILER_VERSION_LAUNCH_PAYLOAD: u16 = 2;
+/// 格式版本3：payload为64字节Ed25519签名 + JSON格式的LaunchPayload，签名由igb-stamp用分发私钥生成
+pub const TRAILER_VERSION_SIGNED_PAYLOAD: u16 = 3;
+pub const TRAILER_MAX_VERSION: u16 = TRAILER_VERSION_SIGNED_PAYLOAD;
+pub const TRAILER_SIGNATURE_SIZE: usize = 64;
+// 分发私钥对应的Ed25519公钥（32字节原始公钥的base64），私钥为PKCS#8格式，只保存在打包机上
+// 轮换时先把新公钥加到列表中并发布新版本，等旧私钥签名的安装包都不再分发后再删除旧公钥
+const TRAILER_PUBLIC_KEYS: [&str; 1] = ["u9nMEpe9Q6mHDJPQmzuoO/5dGSrJvQMUsiGggM4cC98="];
+const TRAILER_MAX_PAYLOAD_SIZE: u32 = 1024 * 1024;
+
+// 旧版本write_resource_id_to_file追加的格式：MAGIC + 大端i32
+const LEGACY_APPEND_MAGIC: [u8; 8] = [0x77, 0x77, 0x77, 0x77, 0xFF, 0xFF, 0xFF, 0xFF];
+// 旧版本发布时写入的格式：在末尾64KB内检索MAGIC，其后为大端i32
+const LEGACY_SEARCH_WINDOW: u64 = 65536;
+const LEGACY_SEARCH_MAGIC: [u8; 16] = [
+    0xea, 0x7f, 0xd6, 0x96, 0x1a, 0x08, 0x71, 0xd3, 0xc1, 0x44, 0x7c, 0x8b, 0x1b, 0xb0, 0xa3, 0x36,
+];
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct LaunchPayload {
+    /// 按顺序交给IGame安装器的资源ID
+    pub resource_ids: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub installer_args: Vec<String>,
+    /// 更新通道
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// 推广渠道或邀请标识
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<String>,
+}
+
+impl LaunchPayload {
+    pub fn from_resource_id(resource_id: i32) -> LaunchPayload {
+        return LaunchPayload {
+            resource_ids: vec![resource_id],
+            ..Default::default()
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PayloadSignature {
+    Valid,
+    Missing,
+    Invalid,
+}
+
+#[derive(Debug)]
+pub struct SignedLaunchPayload {
+    pub payload: LaunchPayload,
+    pub signature: PayloadSignature,
+}
+
+/// 从文件末尾读取的trailer
+pub struct Trailer {
+    pub version: u16,
+    pub payload: Vec<u8>,
+    /// payload在文件中的起始偏移
+    pub offset: u64,
+    /// payload之前的对齐填充长度，去掉trailer时截断到offset - padding
+    pub padding: u16,
+}
+
+impl Trailer {
+    pub fn start(&self) -> u64 {
+        return self.offset - self.padding as u64;
+    }
+}
+
+/// 返回`[payload][footer]`，对齐填充由调用方写在前面
+pub fn encode_trailer(version: u16, payload: &[u8], padding: u16) -> Vec<u8> {
+    let mut buffer = payload.to_vec();
+    buffer.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    buffer.extend_from_slice(&version.to_le_bytes());
+    buffer.extend_from_slice(&padding.to_le_bytes());
+    buffer.extend_from_slice(&TRAILER_MAGIC);
+    return buffer;
+}
+
+fn parse_footer(footer: &[u8]) -> Option<(u32, u32, u16, u16)> {
+    if footer.len() != TRAILER_FOOTER_SIZE || footer[12..] != TRAILER_MAGIC {
+        return None;
+    }
+    let payload_size = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+    let crc = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]);
+    let version = u16::from_le_bytes([footer[8], footer[9]]);
+    let padding = u16::from_le_bytes([footer[10], footer[11]]);
+    return Some((payload_size, crc, version, padding));
+}
+
+fn read_trailer_from<R: Read + Seek>(reader: &mut R, file_size: u64) -> Result<Trailer, String> {
+    if file_size < TRAILER_FOOTER_SIZE as u64 {
+        return Err("文件过小，不包含trailer".to_string());
+    }
+
+    let mut footer = [0u8; TRAILER_FOOTER_SIZE];
+    reader
+        .seek(SeekFrom::Start(file_size - TRAILER_FOOTER_SIZE as u64))
+        .and_then(|_| reader.read_exact(&mut footer))
+        .map_err(|e| format!("读取trailer失败\n{:?}", e))?;
+    let (payload_size, crc, version, padding) = match parse_footer(&footer) {
+        Some(v) => v,
+        None => return Err("未找到trailer".to_string()),
+    };
+
+    if version == 0 || version > TRAILER_MAX_VERSION {
+        return Err(format!("不支持的trailer格式版本：{}", version));
+    }
+    if payload_size > TRAILER_MAX_PAYLOAD_SIZE
+        || payload_size as u64 > file_size - TRAILER_FOOTER_SIZE as u64
+    {
+        return Err(format!("trailer长度不正确：{}", payload_size));
+    }
+
+    let offset = file_size - TRAILER_FOOTER_SIZE as u64 - payload_size as u64;
+    if padding as u64 >= CERTIFICATE_ALIGNMENT || padding as u64 > offset {
+        return Err(format!("trailer对齐填充长度不正确：{}", padding));
+    }
+    let mut payload = vec![0u8; payload_size as usize];
+    reader
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| reader.read_exact(&mut payload))
+        .map_err(|e| format!("读取trailer失败\n{:?}", e))?;
+    if crc32fast::hash(&payload) != crc {
+        return Err("trailer校验失败，文件可能已损坏".to_string());
+    }
+
+    return Ok(Trailer {
+        version,
+        payload,
+        offset,
+        padding,
+    });
+}
+
+/// 读取新格式的trailer，不支持旧格式
+pub fn read_trailer(path: &PathBuf) -> Result<Trailer, String> {
+    let mut file = match fs::File::open(path) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("打开文件失败：{:?}\n{:?}", path, e)),
+    };
+    let file_size = match file.metadata() {
+        Ok(v) => v.len(),
+        Err(e) => return Err(format!("获取文件大小失败：{:?}\n{:?}", path, e)),
+    };
+    return read_trailer_from(&mut file, file_size);
+}
+
+// 返回pattern在src中最后一次出现的位置，重复写入时以最后一次为准
+fn find_last_pattern(src: &[u8], pattern: &[u8]) -> Option<usize> {
+    if pattern.is_empty() || src.len() < pattern.len() {
+        return None;
+    }
+    return src.windows(pattern.len()).rposition(|w| w == pattern);
+}
+
+fn read_legacy_append_resource_id<R: Read + Seek>(
+    reader: &mut R,
+    file_size: u64,
+) -> io::Result<Option<i32>> {
+    if file_size < 12 {
+        return Ok(None);
+    }
+    let mut buffer = [0u8; 12];
+    reader.seek(SeekFrom::Start(file_size - 12))?;
+    reader.read_exact(&mut buffer)?;
+    if buffer[..8] != LEGACY_APPEND_MAGIC {
+        return Ok(None);
+    }
+    return Ok(Some(i32::from_be_bytes([
+        buffer[8], buffer[9], buffer[10], buffer[11],
+    ])));
+}
+
+fn read_legacy_search_resource_id<R: Read + Seek>(
+    reader: &mut R,
+    file_size: u64,
+) -> Result<Option<i32>, String> {
+    // 只在文件末尾的固定窗口内检索，文件小于窗口时检索整个文件
+    let window_size = file_size.min(LEGACY_SEARCH_WINDOW);
+    let window_start = file_size - window_size;
+    let mut window = vec![0u8; window_size as usize];
+    reader
+        .seek(SeekFrom::Start(window_start))
+        .and_then(|_| reader.read_exact(&mut window))
+        .map_err(|e| format!("读取文件末尾{}字节失败\n{:?}", window_size, e))?;
+
+    let position = match find_last_pattern(&window, &LEGACY_SEARCH_MAGIC) {
+        Some(v) => v + LEGACY_SEARCH_MAGIC.len(),
+        None => return Ok(None),
+    };
+    if position + 4 > window.len() {
+        return Err(format!(
+            "资源ID不完整：标记位于偏移{}，其后只有{}字节",
+            window_start + position as u64 - LEGACY_SEARCH_MAGIC.len() as u64,
+            window.len() - position
+        ));
+    }
+
+    return Ok(Some(i32::from_be_bytes([
+        window[position],
+        window[position + 1],
+        window[position + 2],
+        window[position + 3],
+    ])));
+}
+
+fn trusted_public_keys() -> Vec<Vec<u8>> {
+    let keys = TRAILER_PUBLIC_KEYS
+        .iter()
+        .map(|v| base64::decode(v).unwrap());
+    // 测试时额外信任测试私钥对应的公钥
+    #[cfg(test)]
+    let keys = keys.chain(std::iter::once(tests::test_public_key()));
+    return keys.collect();
+}
+
+/// 用内置的公钥校验Ed25519签名，与其中任意一个公钥匹配即为有效
+pub fn verify_payload_signature(data: &[u8], signature: &[u8]) -> bool {
+    return trusted_public_keys().into_iter().any(|public_key| {
+        ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+            .verify(data, signature)
+            .is_ok()
+    });
+}
+
+/// 用PKCS#8格式的Ed25519私钥签名，返回写入trailer的原始JSON和签名
+pub fn sign_launch_payload(
+    payload: &LaunchPayload,
+    private_key: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    // openssl genpkey生成的私钥不带公钥，需要用maybe_unchecked读取
+    let key_pair = match ring::signature::Ed25519KeyPair::from_pkcs8_maybe_unchecked(private_key) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("读取签名私钥失败\n{:?}", e)),
+    };
+    let data = match serde_json::to_vec(payload) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("序列化trailer内容失败\n{:?}", e)),
+    };
+    let signature = key_pair.sign(&data).as_ref().to_vec();
+    return Ok((data, signature));
+}
+
+fn parse_payload_json(data: &[u8]) -> Result<LaunchPayload, String> {
+    let payload: LaunchPayload = match serde_json::from_slice(data) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("反序列化trailer内容失败\n{:?}", e)),
+    };
+    if payload.resource_ids.is_empty() {
+        return Err("trailer中没有资源ID".to_string());
+    }
+    return Ok(payload);
+}
+
+/// 按格式版本解析trailer中的启动信息，不校验签名
+pub fn decode_launch_payload(trailer: &Trailer) -> Result<SignedLaunchPayload, String> {
+    match trailer.version {
+        TRAILER_VERSION_RESOURCE_ID => {
+            if trailer.payload.len() != 4 {
+                return Err(format!("trailer内容长度不正确：{}", trailer.payload.len()));
+            }
+            let p = &trailer.payload;
+            return Ok(SignedLaunchPayload {
+                payload: LaunchPayload::from_resource_id(i32::from_be_bytes([
+                    p[0], p[1], p[2], p[3],
+                ])),
+                signature: PayloadSignature::Missing,
+            });
+        }
+        TRAILER_VERSION_LAUNCH_PAYLOAD => {
+            return Ok(SignedLaunchPayload {
+                payload: parse_payload_json(&trailer.payload)?,
+                signature: PayloadSignature::Missing,
+            });
+        }
+        TRAILER_VERSION_SIGNED_PAYLOAD => {
+            if trailer.payload.len() <= TRAILER_SIGNATURE_SIZE {
+                return Err(format!("trailer内容长度不正确：{}", trailer.payload.len()));
+            }
+            let (signature, data) = trailer.payload.split_at(TRAILER_SIGNATURE_SIZE);
+            let signature = if verify_payload_signature(data, signature) {
+                PayloadSignature::Valid
+            } else {
+                PayloadSignature::Invalid
+            };
+            return Ok(SignedLaunchPayload {
+                payload: parse_payload_json(data)?,
+                signature,
+            });
+        }
+        _ => return Err(format!("不支持的trailer格式版本：{}", trailer.version)),
+    }
+}
+
+/// 读取文件末尾的启动信息，没有新格式的trailer时查找旧格式
+pub fn read_launch_payload(path: &PathBuf) -> Result<SignedLaunchPayload, String> {
+    let mut file = match fs::File::open(path) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("打开文件失败：{:?}\n{:?}", path, e)),
+    };
+    let file_size = match file.metadata() {
+        Ok(v) => v.len(),
+        Err(e) => return Err(format!("获取文件大小失败：{:?}\n{:?}", path, e)),
+    };
+    if file_size == 0 {
+        return Err(format!("文件为空：{:?}", path));
+    }
+
+    match read_trailer_from(&mut file, file_size) {
+        Ok(v) => return decode_launch_payload(&v),
+        Err(e) => {
+            // 找到了footer但内容有问题时直接报错，不再尝试旧格式
+            let mut footer = [0u8; TRAILER_FOOTER_SIZE];
+            if file_size >= TRAILER_FOOTER_SIZE as u64
+                && file
+                    .seek(SeekFrom::Start(file_size - TRAILER_FOOTER_SIZE as u64))
+                    .and_then(|_| file.read_exact(&mut footer))
+                    .is_ok()
+                && parse_footer(&footer).is_some()
+            {
+                return Err(e);
+            }
+        }
+    }
+
+    // 兼容旧格式
+    match read_legacy_append_resource_id(&mut file, file_size) {
+        Ok(Some(v)) => {
+            return Ok(SignedLaunchPayload {
+                payload: LaunchPayload::from_resource_id(v),
+                signature: PayloadSignature::Missing,
+            })
+        }
+        Ok(None) => {}
+        Err(e) => return Err(format!("读取旧格式资源ID失败\n{:?}", e)),
+    }
+    match read_legacy_search_resource_id(&mut file, file_size) {
+        Ok(Some(v)) => {
+            return Ok(SignedLaunchPayload {
+                payload: LaunchPayload::from_resource_id(v),
+                signature: PayloadSignature::Missing,
+            })
+        }
+        Ok(None) => {}
+        Err(e) => return Err(format!("检索旧格式资源ID失败：{}", e)),
+    }
+
+    return Err(format!(
+        "检索资源ID失败：文件末尾{}字节内没有任何格式的标记",
+        file_size.min(LEGACY_SEARCH_WINDOW)
+    ));
+}
+
+// 返回去掉trailer（包括对齐填充）之后的文件长度，没有trailer时返回文件原长度
+fn content_size(path: &PathBuf) -> Result<u64, String> {
+    let mut file = match fs::File::open(path) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("打开文件失败：{:?}\n{:?}", path, e)),
+    };
+    let file_size = match file.metadata() {
+        Ok(v) => v.len(),
+        Err(e) => return Err(format!("获取文件大小失败：{:?}\n{:?}", path, e)),
+    };
+
+    if let Ok(v) = read_trailer_from(&mut file, file_size) {
+        return Ok(v.start());
+    }
+    if let Ok(Some(_)) = read_legacy_append_resource_id(&mut file, file_size) {
+        return Ok(file_size - 12);
+    }
+    return Ok(file_size);
+}
+
+fn open_read_write(path: &PathBuf) -> Result<(fs::File, u64), String> {
+    let file = match fs::File::options().read(true).write(true).open(path) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("获取文件描述符失败：{:?}\n{:?}", path, e)),
+    };
+    let file_size = match file.metadata() {
+        Ok(v) => v.len(),
+        Err(e) => return Err(format!("获取文件大小失败：{:?}\n{:?}", path, e)),
+    };
+    return Ok((file, file_size));
+}
+
+// 已签名的PE文件，证书表位于文件末尾时返回安全目录
+fn find_certificate_table(
+    file: &mut fs::File,
+    file_size: u64,
+) -> Result<Option<SecurityDirectory>, String> {
+    match read_security_directory(file)? {
+        Some(v) if v.end() == file_size => return Ok(Some(v)),
+        _ => return Ok(None),
+    }
+}
+
+/// 去掉文件末尾的trailer，写在证书表内时同时恢复证书表的大小
+pub fn strip_trailer(path: &PathBuf) -> Result<(), String> {
+    let size = content_size(path)?;
+    let (mut file, file_size) = open_read_write(path)?;
+
+    // trailer位于证书表内时，同时恢复证书表大小
+    if let Some(directory) = find_certificate_table(&mut file, file_size)? {
+        if size > directory.offset && size < file_size {
+            write_security_directory_size(&mut file, &directory, (size - directory.offset) as u32)?;
+        }
+    }
+
+    match file.set_len(size) {
+        Err(e) => return Err(format!("截断文件失败：{:?}\n{:?}", path, e)),
+        _ => {}
+    };
+
+    return Ok(());
+}
+
+/// 写入trailer，已有的trailer（包括旧格式追加的）会被替换
+/// 已签名的PE文件会把trailer写在证书表的末尾，不影响Authenticode签名
+pub fn write_trailer(path: &PathBuf, version: u16, payload: &[u8]) -> Result<(), String> {
+    strip_trailer(path)?;
+
+    let (mut file, file_size) = open_read_write(path)?;
+    let directory = find_certificate_table(&mut file, file_size)?;
+    let mut padding = 0;
+    if let Some(v) = &directory {
+        // 在trailer前补零，使证书表大小保持8字节对齐，footer仍然位于文件末尾
+        let trailer_size = (payload.len() + TRAILER_FOOTER_SIZE) as u64;
+        let unaligned = (v.size as u64 + trailer_size) % CERTIFICATE_ALIGNMENT;
+        padding = ((CERTIFICATE_ALIGNMENT - unaligned) % CERTIFICATE_ALIGNMENT) as u16;
+    }
+    let mut buffer = vec![0u8; padding as usize];
+    buffer.extend_from_slice(&encode_trailer(version, payload, padding));
+
+    match file
+        .seek(SeekFrom::Start(file_size))
+        .and_then(|_| file.write_all(&buffer))
+    {
+        Err(e) => return Err(format!("写入文件失败：{:?}\n{:?}", path, e)),
+        _ => {}
+    };
+    if let Some(v) = &directory {
+        write_security_directory_size(&mut file, v, v.size + buffer.len() as u32)?;
+    }
+
+    return Ok(());
+}
+
+/// 写入未签名的启动信息，运行时会忽略其中的资源信息，只用于测试和兼容旧格式
+pub fn write_launch_payload(path: &PathBuf, payload: &LaunchPayload) -> Result<(), String> {
+    let data = match serde_json::to_vec(payload) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("序列化trailer内容失败\n{:?}", e)),
+    };
+    return write_trailer(path, TRAILER_VERSION_LAUNCH_PAYLOAD, &data);
+}
+
+/// data为分发后台签名时使用的原始JSON，写入时不能重新序列化
+pub fn write_signed_launch_payload(
+    path: &PathBuf,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<(), String> {
+    if signature.len() != TRAILER_SIGNATURE_SIZE {
+        return Err(format!("签名长度不正确：{}", signature.len()));
+    }
+    parse_payload_json(data)?;
+    let mut payload = signature.to_vec();
+    payload.extend_from_slice(data);
+    return write_trailer(path, TRAILER_VERSION_SIGNED_PAYLOAD, &payload);
+}
+
+/// 原样复制trailer，保留原来的签名；源文件只有旧格式时按版本2写入，仍然没有签名
+pub fn copy_trailer(src_path: &PathBuf, dst_path: &PathBuf) -> Result<(), String> {
+    match read_trailer(src_path) {
+        Ok(v) => return write_trailer(dst_path, v.version, &v.payload),
+        Err(_) => {
+            let signed_payload = read_launch_payload(src_path)?;
+            return write_launch_payload(dst_path, &signed_payload.payload);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::pe::tests::{build_certificate, build_pe};
+    use lazy_static::lazy_static;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    lazy_static! {
+        // 每次运行测试时随机生成
+        static ref TEST_PRIVATE_KEY: Vec<u8> =
+            Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new())
+                .unwrap()
+                .as_ref()
+                .to_vec();
+    }
+
+    pub(crate) fn test_private_key() -> &'static [u8] {
+        return &TEST_PRIVATE_KEY;
+    }
+
+    pub(crate) fn test_public_key() -> Vec<u8> {
+        return Ed25519KeyPair::from_pkcs8(test_private_key())
+            .unwrap()
+            .public_key()
+            .as_ref()
+            .to_vec();
+    }
+
+    // 每个测试使用自己的文件，避免并行运行时互相影响
+    fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("igb-trailer-test-{}-{}", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        return path;
+    }
+
+    fn sample_payload() -> LaunchPayload {
+        return LaunchPayload {
+            resource_ids: vec![13, 9],
+            installer_args: vec!["--silent".to_string()],
+            channel: Some("beta".to_string()),
+            attribution: Some("invite-42".to_string()),
+        };
+    }
+
+    fn security_directory(path: &PathBuf) -> SecurityDirectory {
+        let mut file = fs::File::open(path).unwrap();
+        return read_security_directory(&mut file).unwrap().unwrap();
+    }
+
+    fn plain_content() -> Vec<u8> {
+        return (0..4096).map(|i| (i % 253) as u8).collect();
+    }
+
+    #[test]
+    fn plain_file_round_trip() {
+        let original = plain_content();
+        let path = temp_file("plain", &original);
+
+        write_launch_payload(&path, &sample_payload()).unwrap();
+        let trailer = read_trailer(&path).unwrap();
+        assert_eq!(trailer.version, TRAILER_VERSION_LAUNCH_PAYLOAD);
+        assert_eq!(trailer.offset, original.len() as u64);
+        assert_eq!(trailer.padding, 0);
+        let signed_payload = read_launch_payload(&path).unwrap();
+        assert_eq!(signed_payload.payload, sample_payload());
+        assert_eq!(signed_payload.signature, PayloadSignature::Missing);
+
+        strip_trailer(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), original);
+        // 没有trailer时什么都不做
+        strip_trailer(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), original);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn resource_id_trailer_round_trip() {
+        let original = plain_content();
+        let path = temp_file("resource-id", &original);
+
+        write_trailer(&path, TRAILER_VERSION_RESOURCE_ID, &(-7i32).to_be_bytes()).unwrap();
+        assert_eq!(
+            read_launch_payload(&path).unwrap().payload,
+            LaunchPayload::from_resource_id(-7)
+        );
+
+        strip_trailer(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), original);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rewrite_replaces_trailer() {
+        let original = plain_content();
+        let path = temp_file("rewrite", &original);
+
+        write_launch_payload(&path, &sample_payload()).unwrap();
+        let payload = LaunchPayload::from_resource_id(99);
+        write_launch_payload(&path, &payload).unwrap();
+        let data = serde_json::to_vec(&payload).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().len(),
+            (original.len() + data.len() + TRAILER_FOOTER_SIZE) as u64
+        );
+        assert_eq!(read_launch_payload(&path).unwrap().payload, payload);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn legacy_append_trailer_is_read_and_replaced() {
+        let original = plain_content();
+        let mut data = original.clone();
+        data.extend_from_slice(&LEGACY_APPEND_MAGIC);
+        data.extend_from_slice(&12i32.to_be_bytes());
+        let path = temp_file("legacy-append", &data);
+
+        let signed_payload = read_launch_payload(&path).unwrap();
+        assert_eq!(signed_payload.payload, LaunchPayload::from_resource_id(12));
+        assert_eq!(signed_payload.signature, PayloadSignature::Missing);
+
+        write_launch_payload(&path, &sample_payload()).unwrap();
+        assert_eq!(
+            read_launch_payload(&path).unwrap().payload,
+            sample_payload()
+        );
+        strip_trailer(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), original);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn copy_trailer_keeps_payload() {
+        let mut legacy = plain_content();
+        legacy.extend_from_slice(&LEGACY_APPEND_MAGIC);
+        legacy.extend_from_slice(&12i32.to_be_bytes());
+        let src_path = temp_file("copy-src", &legacy);
+        let dst_path = temp_file("copy-dst", &plain_content());
+
+        // 旧格式按版本2写入
+        copy_trailer(&src_path, &dst_path).unwrap();
+        assert_eq!(
+            read_trailer(&dst_path).unwrap().version,
+            TRAILER_VERSION_LAUNCH_PAYLOAD
+        );
+        assert_eq!(
+            read_launch_payload(&dst_path).unwrap().payload,
+            LaunchPayload::from_resource_id(12)
+        );
+
+        // 新格式原样复制
+        write_trailer(&src_path, TRAILER_VERSION_RESOURCE_ID, &5i32.to_be_bytes()).unwrap();
+        copy_trailer(&src_path, &dst_path).unwrap();
+        let trailer = read_trailer(&dst_path).unwrap();
+        assert_eq!(trailer.version, TRAILER_VERSION_RESOURCE_ID);
+        assert_eq!(trailer.payload, 5i32.to_be_bytes());
+
+        fs::remove_file(&src_path).unwrap();
+        fs::remove_file(&dst_path).unwrap();
+    }
+
+    #[test]
+    fn signed_payload_survives_copy_trailer() {
+        let src_path = temp_file("signed-src", &plain_content());
+        let dst_path = temp_file("signed-dst", &plain_content());
+        let (data, signature) = sign_launch_payload(&sample_payload(), test_private_key()).unwrap();
+        write_signed_launch_payload(&src_path, &data, &signature).unwrap();
+        let signed_payload = read_launch_payload(&src_path).unwrap();
+        assert_eq!(signed_payload.signature, PayloadSignature::Valid);
+        assert_eq!(signed_payload.payload, sample_payload());
+
+        // 自更新时复制到新版本，签名仍然有效
+        copy_trailer(&src_path, &dst_path).unwrap();
+        let signed_payload = read_launch_payload(&dst_path).unwrap();
+        assert_eq!(signed_payload.signature, PayloadSignature::Valid);
+        assert_eq!(signed_payload.payload, sample_payload());
+
+        // 其他私钥的签名无效
+        let other_key = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+        let (data, signature) = sign_launch_payload(&sample_payload(), other_key.as_ref()).unwrap();
+        write_signed_launch_payload(&src_path, &data, &signature).unwrap();
+        assert_eq!(
+            read_launch_payload(&src_path).unwrap().signature,
+            PayloadSignature::Invalid
+        );
+
+        fs::remove_file(&src_path).unwrap();
+        fs::remove_file(&dst_path).unwrap();
+    }
+
+    #[test]
+    fn rejects_damaged_trailer() {
+        let mut data = plain_content();
+        data.extend_from_slice(&encode_trailer(
+            TRAILER_VERSION_LAUNCH_PAYLOAD,
+            &serde_json::to_vec(&sample_payload()).unwrap(),
+            0,
+        ));
+        let path = temp_file("damaged", &data);
+
+        // 修改payload后CRC不匹配，不会再按旧格式检索
+        let index = data.len() - TRAILER_FOOTER_SIZE - 2;
+        let mut damaged = data.clone();
+        damaged[index] ^= 0xff;
+        fs::write(&path, &damaged).unwrap();
+        assert!(read_launch_payload(&path).unwrap_err().contains("校验失败"));
+
+        // 不支持的格式版本
+        let mut unsupported = data.clone();
+        let index = data.len() - TRAILER_FOOTER_SIZE + 8;
+        unsupported[index..index + 2].copy_from_slice(&(TRAILER_MAX_VERSION + 1).to_le_bytes());
+        fs::write(&path, &unsupported).unwrap();
+        assert!(read_launch_payload(&path)
+            .unwrap_err()
+            .contains("不支持的trailer格式版本"));
+
+        // payload长度超过文件长度
+        let mut oversized = data.clone();
+        let index = data.len() - TRAILER_FOOTER_SIZE;
+        oversized[index..index + 4].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        fs::write(&path, &oversized).unwrap();
+        assert!(read_launch_payload(&path)
+            .unwrap_err()
+            .contains("trailer长度不正确"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    // 填充字节不会和任何格式的标记重合
+    fn legacy_file(size: usize, markers: &[(usize, i32)]) -> Vec<u8> {
+        let mut data = vec![0x5au8; size];
+        for (offset, resource_id) in markers {
+            let mut marker = LEGACY_SEARCH_MAGIC.to_vec();
+            marker.extend_from_slice(&resource_id.to_be_bytes());
+            for (i, v) in marker.iter().enumerate() {
+                if offset + i < size {
+                    data[offset + i] = *v;
+                }
+            }
+        }
+        return data;
+    }
+
+    fn search_legacy(data: &[u8]) -> Result<Option<i32>, String> {
+        return read_legacy_search_resource_id(&mut io::Cursor::new(data), data.len() as u64);
+    }
+
+    #[test]
+    fn files_of_any_size_without_marker() {
+        for size in [0, 1, 15, 16, 19, 20, 65535, 65536, 65537] {
+            let data = legacy_file(size, &[]);
+            assert_eq!(search_legacy(&data), Ok(None), "size {}", size);
+            let error = read_trailer_from(&mut io::Cursor::new(&data), size as u64)
+                .err()
+                .unwrap();
+            if size < TRAILER_FOOTER_SIZE {
+                assert!(error.contains("文件过小"), "size {}", size);
+            } else {
+                assert!(error.contains("未找到trailer"), "size {}", size);
+            }
+
+            let path = temp_file(&format!("no-marker-{}", size), &data);
+            let error = read_launch_payload(&path).err().unwrap();
+            if size == 0 {
+                assert!(error.contains("文件为空"));
+            } else {
+                assert!(error.contains("检索资源ID失败"), "size {}", size);
+            }
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn files_around_marker_size() {
+        // 15字节：标记不完整；16字节：只有标记；19字节：资源ID缺1字节；20字节：完整
+        assert_eq!(search_legacy(&legacy_file(15, &[(0, 7)])), Ok(None));
+        for size in [16, 19] {
+            let error = search_legacy(&legacy_file(size, &[(0, 7)])).unwrap_err();
+            assert!(error.contains("资源ID不完整"), "size {}", size);
+            assert!(
+                error.contains(&format!("其后只有{}字节", size - 16)),
+                "{}",
+                error
+            );
+        }
+        assert_eq!(search_legacy(&legacy_file(20, &[(0, 7)])), Ok(Some(7)));
+
+        let path = temp_file("marker-20", &legacy_file(20, &[(0, 7)]));
+        assert_eq!(
+            read_launch_payload(&path).unwrap().payload,
+            LaunchPayload::from_resource_id(7)
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn marker_at_every_offset() {
+        let size = 256;
+        for offset in 0..size {
+            let data = legacy_file(size, &[(offset, offset as i32)]);
+            let result = search_legacy(&data);
+            if offset + 20 <= size {
+                assert_eq!(result, Ok(Some(offset as i32)), "offset {}", offset);
+            } else if offset + 16 <= size {
+                // 标记在文件末尾被截断，资源ID不完整
+                let error = result.unwrap_err();
+                assert!(error.contains("资源ID不完整"), "offset {}", offset);
+                assert!(error.contains(&format!("偏移{}", offset)), "{}", error);
+            } else {
+                assert_eq!(result, Ok(None), "offset {}", offset);
+            }
+        }
+    }
+
+    #[test]
+    fn marker_around_window_boundary() {
+        for size in [65535, 65536, 65537, 65536 + 20, 131072] {
+            let window_start = size - size.min(LEGACY_SEARCH_WINDOW as usize);
+            // 窗口开头和末尾的标记都能找到
+            for offset in [window_start, window_start + 1, size / 2, size - 20] {
+                if offset < window_start {
+                    continue;
+                }
+                let data = legacy_file(size, &[(offset, 3)]);
+                assert_eq!(
+                    search_legacy(&data),
+                    Ok(Some(3)),
+                    "size {} offset {}",
+                    size,
+                    offset
+                );
+            }
+            // 标记全部或部分位于窗口之外时不检索
+            for back in [1, 8, 16, 100] {
+                if window_start < back {
+                    continue;
+                }
+                let data = legacy_file(size, &[(window_start - back, 3)]);
+                assert_eq!(
+                    search_legacy(&data),
+                    Ok(None),
+                    "size {} offset {}",
+                    size,
+                    window_start - back
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn duplicate_markers_use_the_last_one() {
+        let data = legacy_file(1024, &[(10, 1), (500, 2), (1000, 3)]);
+        assert_eq!(search_legacy(&data), Ok(Some(3)));
+
+        // 相同的标记重复写入
+        let data = legacy_file(1024, &[(10, 4), (30, 4), (50, 4)]);
+        assert_eq!(search_legacy(&data), Ok(Some(4)));
+
+        // 相邻的标记
+        let data = legacy_file(1024, &[(100, 5), (120, 6)]);
+        assert_eq!(search_legacy(&data), Ok(Some(6)));
+
+        // 最后一个标记不完整时报错，不会退回到前面的标记
+        let data = legacy_file(1024, &[(100, 5), (1006, 6)]);
+        assert!(search_legacy(&data).unwrap_err().contains("资源ID不完整"));
+
+        // 窗口外的标记被忽略
+        let data = legacy_file(70000, &[(10, 1), (69000, 2)]);
+        assert_eq!(search_legacy(&data), Ok(Some(2)));
+        let data = legacy_file(70000, &[(10, 1)]);
+        assert_eq!(search_legacy(&data), Ok(None));
+    }
+
+    #[test]
+    fn signed_pe_round_trip_restores_original_bytes() {
+        // 533：证书和证书表都没有对齐；536：证书长度533，证书表含对齐填充
+        let tables = [
+            build_certificate(533),
+            {
+                let mut v = build_certificate(533);
+                v.resize(536, 0);
+                v
+            },
+            build_certificate(1024),
+        ];
+        for (i, table) in tables.iter().enumerate() {
+            for pe32_plus in [false, true] {
+                let original = build_pe(pe32_plus, table);
+                let path = temp_file(&format!("pe-{}-{}", i, pe32_plus), &original);
+
+                write_launch_payload(&path, &sample_payload()).unwrap();
+                let directory = security_directory(&path);
+                let file_size = fs::metadata(&path).unwrap().len();
+                assert_eq!(directory.end(), file_size);
+                assert_eq!(directory.size as u64 % CERTIFICATE_ALIGNMENT, 0);
+                assert_eq!(
+                    read_launch_payload(&path).unwrap().payload,
+                    sample_payload()
+                );
+
+                strip_trailer(&path).unwrap();
+                assert_eq!(fs::read(&path).unwrap(), original);
+                fs::remove_file(&path).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn signed_pe_rewrite_replaces_trailer() {
+        let original = build_pe(true, &build_certificate(533));
+        let path = temp_file("pe-rewrite", &original);
+
+        write_launch_payload(&path, &LaunchPayload::from_resource_id(1)).unwrap();
+        // 长度不同的payload会改变对齐填充
+        for resource_ids in [vec![2], vec![3, 4, 5], vec![123456789]] {
+            let payload = LaunchPayload {
+                resource_ids,
+                ..Default::default()
+            };
+            write_launch_payload(&path, &payload).unwrap();
+            assert_eq!(read_launch_payload(&path).unwrap().payload, payload);
+            assert_eq!(
+                security_directory(&path).end(),
+                fs::metadata(&path).unwrap().len()
+            );
+        }
+
+        strip_trailer(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), original);
+        assert_eq!(security_directory(&path).size, 533);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unsigned_pe_gets_plain_trailer() {
+        let original = build_pe(false, &[]);
+        let path = temp_file("pe-unsigned", &original);
+
+        write_launch_payload(&path, &sample_payload()).unwrap();
+        let trailer = read_trailer(&path).unwrap();
+        assert_eq!(trailer.offset, original.len() as u64);
+        assert_eq!(trailer.padding, 0);
+
+        strip_trailer(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), original);
+        fs::remove_file(&path).unwrap();
+    }
+}
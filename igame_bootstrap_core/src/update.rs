@@ -0,0 +1,601 @@
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::api::{
+    get_channel_download_url, get_update_info, get_update_patch, ProviderGroup, UpdateInfo,
+};
+use crate::channel::{
+    get_installed_channel, get_update_channel, set_installed_channel, UpdateChannel,
+};
+use crate::config::{get_config_value, get_installation_id, set_config_value};
+use crate::continuation::{forwarded_args, write_continuation};
+use crate::crypto::file_sha256;
+use crate::error::process_error;
+use crate::file::{apply_zstd_patch, extract_tzst, try_copy_file, try_move_file, try_remove_path};
+use crate::net::download_file;
+use crate::platform::ProgressNotifier;
+use crate::process::{build_args, exit, start_exe_as_admin};
+use crate::static_var;
+use crate::time::unix_timestamp;
+use crate::trailer::{copy_trailer, strip_trailer};
+use crate::version::compare_version;
+use crate::workspace::{get_random_workspace_dir_path, get_workspace_file_path};
+
+const SKIPPED_VERSION_CONFIG_NAME: &str = "SkippedVersion";
+// 安装所属的推送分组，由测试人员手动设置
+const ROLLOUT_COHORT_CONFIG_NAME: &str = "RolloutCohort";
+const REMIND_AFTER_CONFIG_NAME: &str = "RemindUpdateAfter";
+// 选择稍后提醒后，这段时间内不再提示可选更新
+const UPDATE_REMIND_LATER_SECONDS: u64 = 24 * 60 * 60;
+
+/// 检查更新的结果
+pub enum UpdateRequirement {
+    NotNeeded,
+    /// 可以由用户选择是否更新
+    Optional(UpdateInfo),
+    Mandatory(UpdateInfo),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum UpdateChoice {
+    UpdateNow,
+    SkipVersion,
+    RemindLater,
+}
+
+impl Default for UpdateChoice {
+    fn default() -> Self {
+        return UpdateChoice::RemindLater;
+    }
+}
+
+// 按安装标识和版本号计算0到99的分桶，同一安装对同一版本的结果固定
+fn rollout_bucket(installation_id: &str, version: &str) -> u8 {
+    let digest = ring::digest::digest(
+        &ring::digest::SHA256,
+        format!("{}:{}", installation_id, version).as_bytes(),
+    );
+    let bytes = digest.as_ref();
+    let value = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    return (value % 100) as u8;
+}
+
+fn is_in_rollout(update_info: &UpdateInfo) -> bool {
+    if let Some(cohort) = get_config_value(ROLLOUT_COHORT_CONFIG_NAME) {
+        if update_info.rollout_cohorts.contains(&cohort) {
+            return true;
+        }
+    }
+    // 暂停推送时，还没有收到的用户不再更新
+    if update_info.rollout_paused {
+        return false;
+    }
+    let percentage = match update_info.rollout_percentage {
+        Some(v) => v.min(100),
+        None => return true,
+    };
+    return rollout_bucket(&get_installation_id(), &update_info.latest_version) < percentage;
+}
+
+/// 根据更新信息、推送比例和用户的选择判断是否需要更新
+pub fn check_update() -> UpdateRequirement {
+    let channel = get_update_channel();
+    let update_info = match get_update_info(8, &channel) {
+        Some(v) => v,
+        None => return UpdateRequirement::NotNeeded,
+    };
+    let current_version = env!("CARGO_PKG_VERSION");
+    // 这个版本之前更新失败并已还原，不再重复更新
+    if let Some(v) = read_update_marker(&UpdateFiles::current().marker_path) {
+        if v.state == UpdateState::RolledBack && v.new_version == update_info.latest_version {
+            return UpdateRequirement::NotNeeded;
+        }
+    }
+    let ordering = match compare_version(&update_info.latest_version, current_version) {
+        Ok(v) => v,
+        Err(e) => {
+            process_error(format!("比较版本失败\n{}", e), false, true, true, false);
+            return UpdateRequirement::NotNeeded;
+        }
+    };
+
+    // 切换了更新通道时，只要版本不同就更新，允许降级到该通道的版本
+    if get_installed_channel() != channel {
+        if ordering == Ordering::Equal {
+            let _ = set_installed_channel(channel);
+            return UpdateRequirement::NotNeeded;
+        }
+        return UpdateRequirement::Mandatory(update_info);
+    }
+    if ordering != Ordering::Greater {
+        return UpdateRequirement::NotNeeded;
+    }
+
+    // 低于最低支持版本时不受推送比例限制
+    if let Some(min_version) = &update_info.min_version {
+        if let Ok(Ordering::Less) = compare_version(current_version, min_version) {
+            return UpdateRequirement::Mandatory(update_info);
+        }
+    }
+    if !is_in_rollout(&update_info) {
+        return UpdateRequirement::NotNeeded;
+    }
+    if get_config_value(SKIPPED_VERSION_CONFIG_NAME).as_ref() == Some(&update_info.latest_version) {
+        return UpdateRequirement::NotNeeded;
+    }
+    let remind_after = get_config_value(REMIND_AFTER_CONFIG_NAME)
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0);
+    if unix_timestamp() < remind_after {
+        return UpdateRequirement::NotNeeded;
+    }
+    return UpdateRequirement::Optional(update_info);
+}
+
+pub fn skip_update_version(version: &str) {
+    match set_config_value(SKIPPED_VERSION_CONFIG_NAME, version) {
+        Err(e) => process_error(e, false, true, false, false),
+        _ => {}
+    };
+}
+
+pub fn remind_update_later() {
+    let remind_after = unix_timestamp() + UPDATE_REMIND_LATER_SECONDS;
+    match set_config_value(REMIND_AFTER_CONFIG_NAME, &remind_after.to_string()) {
+        Err(e) => process_error(e, false, true, false, false),
+        _ => {}
+    };
+}
+
+// 差分更新得到的新版本文件，安装时优先使用
+const PATCHED_EXE_NAME: &str = "IGameBootstrapper_patched.exe";
+
+// 没有可用的补丁时返回false
+fn try_download_patch_update(
+    channel: &UpdateChannel,
+    rate: Arc<Mutex<u8>>,
+    rate_sender: impl ProgressNotifier,
+) -> Result<bool, String> {
+    let patch = match get_update_patch(8, env!("CARGO_PKG_VERSION"), channel) {
+        Some(v) => v,
+        None => return Ok(false),
+    };
+    let patch_file_name = "IGameBootstrapper.patch";
+    download_file(
+        patch.download_url.as_str(),
+        patch_file_name,
+        rate,
+        rate_sender,
+    )?;
+
+    // 补丁基于发布时的原始文件生成，需要先去掉自身末尾的启动信息
+    let self_exe_path = PathBuf::from((*static_var::CURRENT_EXE_PATH).clone());
+    let base_path = get_workspace_file_path("IGameBootstrapper_base.exe");
+    let patch_path = get_workspace_file_path(patch_file_name);
+    let patched_path = get_workspace_file_path(PATCHED_EXE_NAME);
+    let result = try_copy_file(&self_exe_path, &base_path)
+        .and_then(|_| strip_trailer(&base_path))
+        .and_then(|_| apply_zstd_patch(&base_path, &patch_path, &patched_path))
+        .and_then(|_| file_sha256(&patched_path))
+        .and_then(|v| {
+            if v != patch.sha256 {
+                return Err(format!(
+                    "补丁生成的文件校验失败：{}，应为{}",
+                    v, patch.sha256
+                ));
+            }
+            return Ok(());
+        });
+    let _ = try_remove_path(&base_path);
+    let _ = try_remove_path(&patch_path);
+    if let Err(e) = result {
+        let _ = try_remove_path(&patched_path);
+        return Err(e);
+    }
+
+    return Ok(true);
+}
+
+/// 下载新版本到工作目录
+pub fn download_update(
+    rate: Arc<Mutex<u8>>,
+    rate_sender: impl ProgressNotifier + Copy,
+) -> Result<(), String> {
+    let channel = get_update_channel();
+    // 优先使用差分补丁，没有补丁或应用失败时下载完整的更新文件
+    match try_download_patch_update(&channel, rate.clone(), rate_sender) {
+        Ok(true) => return Ok(()),
+        Ok(false) => {}
+        Err(e) => {
+            process_error(
+                format!("差分更新失败，改为完整更新\n{}", e),
+                false,
+                true,
+                true,
+                false,
+            );
+            {
+                *rate.lock().unwrap() = 0;
+            }
+            rate_sender.notify();
+        }
+    };
+
+    let download_url = get_channel_download_url(8, &ProviderGroup::Fast, &channel);
+    let file_name = "IGameBootstrapper.tzst";
+    download_file(download_url.as_str(), file_name, rate, rate_sender)?;
+    return Ok(());
+}
+
+// 新版本启动后需要在这个时间内确认运行正常，否则还原旧版本
+const UPDATE_HEALTH_TIMEOUT_SECONDS: u64 = 60;
+
+#[derive(PartialEq, Debug)]
+enum UpdateState {
+    Pending,
+    Healthy,
+    RolledBack,
+}
+
+struct UpdateMarker {
+    state: UpdateState,
+    old_version: String,
+    new_version: String,
+    timestamp: u64,
+}
+
+// 自身和更新过程中使用的文件，都在自身所在的目录下
+struct UpdateFiles {
+    exe_path: PathBuf,
+    old_exe_path: PathBuf,
+    failed_exe_path: PathBuf,
+    marker_path: PathBuf,
+}
+
+impl UpdateFiles {
+    fn new(exe_path: &str) -> UpdateFiles {
+        let stem = &exe_path[..exe_path.len() - 4];
+        return UpdateFiles {
+            exe_path: PathBuf::from(exe_path),
+            old_exe_path: PathBuf::from(format!("{}_old.exe", stem)),
+            failed_exe_path: PathBuf::from(format!("{}_failed.exe", stem)),
+            marker_path: PathBuf::from(format!("{}_update.marker", stem)),
+        };
+    }
+
+    fn current() -> UpdateFiles {
+        return UpdateFiles::new(&static_var::CURRENT_EXE_PATH);
+    }
+}
+
+fn read_update_marker(path: &PathBuf) -> Option<UpdateMarker> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut marker = UpdateMarker {
+        state: UpdateState::Pending,
+        old_version: String::new(),
+        new_version: String::new(),
+        timestamp: 0,
+    };
+    for line in content.lines() {
+        match line.split_once('=') {
+            Some(("state", "pending")) => marker.state = UpdateState::Pending,
+            Some(("state", "healthy")) => marker.state = UpdateState::Healthy,
+            Some(("state", "rolled_back")) => marker.state = UpdateState::RolledBack,
+            Some(("old_version", v)) => marker.old_version = v.to_string(),
+            Some(("new_version", v)) => marker.new_version = v.to_string(),
+            Some(("timestamp", v)) => marker.timestamp = v.parse::<u64>().unwrap_or(0),
+            _ => {}
+        }
+    }
+    return Some(marker);
+}
+
+fn write_update_marker(path: &PathBuf, marker: &UpdateMarker) -> Result<(), String> {
+    let state = match marker.state {
+        UpdateState::Pending => "pending",
+        UpdateState::Healthy => "healthy",
+        UpdateState::RolledBack => "rolled_back",
+    };
+    let content = format!(
+        "state={}\nold_version={}\nnew_version={}\ntimestamp={}\n",
+        state, marker.old_version, marker.new_version, marker.timestamp
+    );
+    match std::fs::write(path, content) {
+        Err(e) => return Err(format!("写入更新状态失败：{:?}\n{:?}", path, e)),
+        _ => {}
+    };
+    return Ok(());
+}
+
+// 新版本没有确认正常运行时，把旧版本换回来
+fn rollback_update(files: &UpdateFiles, marker: &mut UpdateMarker) -> Result<(), String> {
+    if !files.old_exe_path.exists() {
+        return Err("旧版本文件不存在，无法还原".to_string());
+    }
+    // 新版本可能仍在运行，只能改名不能删除
+    try_move_file(&files.exe_path, &files.failed_exe_path)?;
+    try_move_file(&files.old_exe_path, &files.exe_path)?;
+    marker.state = UpdateState::RolledBack;
+    write_update_marker(&files.marker_path, marker)?;
+    return Ok(());
+}
+
+/// 用下载的新版本替换自身并以新版本重新启动
+pub fn install_update() -> Result<(), String> {
+    let self_exe_path_string = (*static_var::CURRENT_EXE_PATH).clone();
+    let self_exe_path = PathBuf::from(self_exe_path_string.clone());
+    let channel = get_update_channel();
+    let new_version = match get_update_info(8, &channel) {
+        Some(v) => v.latest_version,
+        None => return Err("获取新版本信息失败".to_string()),
+    };
+    let patched_exe_path = get_workspace_file_path(PATCHED_EXE_NAME);
+    let dst_dir = get_random_workspace_dir_path();
+    let download_exe_path = if patched_exe_path.exists() {
+        patched_exe_path
+    } else {
+        let tzst_path = get_workspace_file_path("IGameBootstrapper.tzst");
+        extract_tzst(&tzst_path, &dst_dir)?;
+        try_remove_path(&tzst_path)?;
+        let mut v = dst_dir.clone();
+        v.push("IGameBootstrapper.exe");
+        v
+    };
+    let files = UpdateFiles::current();
+    try_move_file(&self_exe_path, &files.old_exe_path)?;
+    try_copy_file(&download_exe_path, &self_exe_path)?;
+    try_remove_path(&download_exe_path)?;
+    if dst_dir.exists() {
+        try_remove_path(&dst_dir)?;
+    }
+    copy_trailer(&files.old_exe_path, &self_exe_path)?;
+
+    let mut marker = UpdateMarker {
+        state: UpdateState::Pending,
+        old_version: env!("CARGO_PKG_VERSION").to_string(),
+        new_version,
+        timestamp: unix_timestamp(),
+    };
+    write_update_marker(&files.marker_path, &marker)?;
+    // 新进程沿用原始参数和本次运行的状态，不再重复检查更新
+    let mut args = forwarded_args();
+    args.push(channel.to_arg());
+    args.push(write_continuation()?);
+    start_exe_as_admin(
+        &self_exe_path_string,
+        (*static_var::CURRENT_DIR_PATH).as_str(),
+        &build_args(&args),
+    );
+
+    // 等待新版本确认正常运行，超时则还原旧版本并重新启动
+    for _ in 0..UPDATE_HEALTH_TIMEOUT_SECONDS * 2 {
+        std::thread::sleep(Duration::from_millis(500));
+        match read_update_marker(&files.marker_path) {
+            Some(v) if v.state == UpdateState::Pending => continue,
+            _ => exit(0),
+        }
+    }
+    rollback_update(&files, &mut marker)?;
+    start_exe_as_admin(
+        &self_exe_path_string,
+        (*static_var::CURRENT_DIR_PATH).as_str(),
+        &build_args(&forwarded_args()),
+    );
+    exit(0);
+
+    return Ok(());
+}
+
+/// 界面初始化后调用，运行到这里说明新版本可以正常工作
+pub fn confirm_update_healthy() {
+    if mark_update_healthy(&UpdateFiles::current(), env!("CARGO_PKG_VERSION")) {
+        let _ = set_installed_channel(get_update_channel());
+    }
+}
+
+// 记录中的新版本来自接口，可能带v前缀或构建信息，按版本号比较
+fn mark_update_healthy(files: &UpdateFiles, current_version: &str) -> bool {
+    let mut marker = match read_update_marker(&files.marker_path) {
+        Some(v) => v,
+        None => return false,
+    };
+    if marker.state != UpdateState::Pending
+        || compare_version(&marker.new_version, current_version) != Ok(Ordering::Equal)
+    {
+        return false;
+    }
+    marker.state = UpdateState::Healthy;
+    let _ = write_update_marker(&files.marker_path, &marker);
+    return true;
+}
+
+/// 启动时检查上次更新的状态，超时未确认的更新会被回滚
+pub fn try_check_update_state() -> Result<(), String> {
+    // 上次更新后一直没有确认运行正常，还原旧版本后重新启动
+    if check_update_state(&UpdateFiles::current(), unix_timestamp())? {
+        start_exe_as_admin(
+            (*static_var::CURRENT_EXE_PATH).as_str(),
+            (*static_var::CURRENT_DIR_PATH).as_str(),
+            &build_args(&forwarded_args()),
+        );
+        exit(0);
+    }
+    return Ok(());
+}
+
+// 已还原旧版本、需要重新启动时返回true
+fn check_update_state(files: &UpdateFiles, now: u64) -> Result<bool, String> {
+    match read_update_marker(&files.marker_path) {
+        Some(mut v) if v.state == UpdateState::Pending => {
+            if now.saturating_sub(v.timestamp) > UPDATE_HEALTH_TIMEOUT_SECONDS {
+                rollback_update(files, &mut v)?;
+                return Ok(true);
+            }
+            // 等待确认期间保留旧版本
+            return Ok(false);
+        }
+        Some(v) if v.state == UpdateState::Healthy => {
+            try_remove_path(&files.marker_path)?;
+        }
+        // 已还原的记录保留下来，避免再次更新到同一个版本
+        _ => {}
+    }
+
+    if files.old_exe_path.exists() {
+        try_remove_path(&files.old_exe_path)?;
+    }
+    // 还原前的新版本可能仍卡在运行中，删除失败时留到下次启动再删
+    if files.failed_exe_path.exists() {
+        let _ = try_remove_path(&files.failed_exe_path);
+    }
+
+    return Ok(false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个测试使用自己的目录，避免并行运行时互相影响
+    fn update_files(name: &str) -> UpdateFiles {
+        let dir_path =
+            std::env::temp_dir().join(format!("igb-update-test-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir_path);
+        std::fs::create_dir_all(&dir_path).unwrap();
+        return UpdateFiles::new(dir_path.join("IGameBootstrapper.exe").to_str().unwrap());
+    }
+
+    fn pending_marker(new_version: &str, timestamp: u64) -> UpdateMarker {
+        return UpdateMarker {
+            state: UpdateState::Pending,
+            old_version: "0.2.6".to_string(),
+            new_version: new_version.to_string(),
+            timestamp,
+        };
+    }
+
+    // 模拟install_update之后的状态：新版本已替换自身，旧版本改名保留
+    fn installed_update(files: &UpdateFiles, marker: &UpdateMarker) {
+        std::fs::write(&files.exe_path, "new").unwrap();
+        std::fs::write(&files.old_exe_path, "old").unwrap();
+        write_update_marker(&files.marker_path, marker).unwrap();
+    }
+
+    fn read(path: &PathBuf) -> String {
+        return std::fs::read_to_string(path).unwrap();
+    }
+
+    #[test]
+    fn marker_round_trip() {
+        let files = update_files("marker");
+        assert!(read_update_marker(&files.marker_path).is_none());
+
+        for state in [
+            UpdateState::Pending,
+            UpdateState::Healthy,
+            UpdateState::RolledBack,
+        ] {
+            let mut marker = pending_marker("0.2.7", 1700000000);
+            marker.state = state;
+            write_update_marker(&files.marker_path, &marker).unwrap();
+            let read_marker = read_update_marker(&files.marker_path).unwrap();
+            assert_eq!(read_marker.state, marker.state);
+            assert_eq!(read_marker.old_version, "0.2.6");
+            assert_eq!(read_marker.new_version, "0.2.7");
+            assert_eq!(read_marker.timestamp, 1700000000);
+        }
+
+        // 不认识的行被忽略，时间戳不正确时为0
+        std::fs::write(&files.marker_path, "state=healthy\nfoo=bar\ntimestamp=x\n").unwrap();
+        let read_marker = read_update_marker(&files.marker_path).unwrap();
+        assert_eq!(read_marker.state, UpdateState::Healthy);
+        assert_eq!(read_marker.timestamp, 0);
+    }
+
+    #[test]
+    fn pending_update_keeps_old_version_until_timeout() {
+        let files = update_files("pending");
+        installed_update(&files, &pending_marker("0.2.7", 1000));
+
+        let now = 1000 + UPDATE_HEALTH_TIMEOUT_SECONDS;
+        assert_eq!(check_update_state(&files, now), Ok(false));
+        assert_eq!(read(&files.exe_path), "new");
+        assert_eq!(read(&files.old_exe_path), "old");
+        assert_eq!(
+            read_update_marker(&files.marker_path).unwrap().state,
+            UpdateState::Pending
+        );
+    }
+
+    #[test]
+    fn timed_out_update_is_rolled_back() {
+        let files = update_files("timeout");
+        installed_update(&files, &pending_marker("0.2.7", 1000));
+
+        let now = 1001 + UPDATE_HEALTH_TIMEOUT_SECONDS;
+        assert_eq!(check_update_state(&files, now), Ok(true));
+        assert_eq!(read(&files.exe_path), "old");
+        assert_eq!(read(&files.failed_exe_path), "new");
+        assert!(!files.old_exe_path.exists());
+        assert_eq!(
+            read_update_marker(&files.marker_path).unwrap().state,
+            UpdateState::RolledBack
+        );
+
+        // 重新启动后清理失败的版本，保留还原记录
+        assert_eq!(check_update_state(&files, now), Ok(false));
+        assert!(!files.failed_exe_path.exists());
+        assert_eq!(
+            read_update_marker(&files.marker_path).unwrap().state,
+            UpdateState::RolledBack
+        );
+    }
+
+    #[test]
+    fn healthy_update_removes_old_version_and_marker() {
+        let files = update_files("healthy");
+        installed_update(&files, &pending_marker("v0.2.7", 1000));
+
+        assert!(mark_update_healthy(&files, "0.2.7"));
+        assert_eq!(
+            read_update_marker(&files.marker_path).unwrap().state,
+            UpdateState::Healthy
+        );
+        // 已确认后不会再超时还原
+        assert_eq!(check_update_state(&files, u64::MAX), Ok(false));
+        assert_eq!(read(&files.exe_path), "new");
+        assert!(!files.old_exe_path.exists());
+        assert!(!files.marker_path.exists());
+    }
+
+    #[test]
+    fn healthy_check_compares_versions() {
+        let files = update_files("versions");
+        let cases = [
+            ("0.2.7", true),
+            ("v0.2.7", true),
+            ("0.2.7.0", true),
+            ("0.2.7+build.3", true),
+            ("0.2.8", false),
+            ("0.2.7-beta.1", false),
+            ("", false),
+        ];
+        for (new_version, healthy) in cases {
+            write_update_marker(&files.marker_path, &pending_marker(new_version, 1000)).unwrap();
+            assert_eq!(
+                mark_update_healthy(&files, "0.2.7"),
+                healthy,
+                "{}",
+                new_version
+            );
+        }
+
+        // 只确认等待中的更新
+        let mut marker = pending_marker("0.2.7", 1000);
+        marker.state = UpdateState::RolledBack;
+        write_update_marker(&files.marker_path, &marker).unwrap();
+        assert!(!mark_update_healthy(&files, "0.2.7"));
+    }
+}
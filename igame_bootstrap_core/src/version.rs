@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+
+/// 按semver规则比较版本号：`x.y.z[-预发布标识][+构建信息]`
+/// 带预发布标识的版本低于同号的正式版本，例如0.3.0-beta.1 < 0.3.0
+#[derive(Debug)]
+pub struct Version {
+    pub numbers: Vec<u64>,
+    pub pre_release: Vec<String>,
+}
+
+impl Version {
+    pub fn parse(version: &str) -> Result<Version, String> {
+        let version = version.trim().trim_start_matches(|c| c == 'v' || c == 'V');
+        // 构建信息不参与比较
+        let version = match version.split_once('+') {
+            Some((v, _)) => v,
+            None => version,
+        };
+        let (numbers_part, pre_release_part) = match version.split_once('-') {
+            Some((n, p)) => (n, Some(p)),
+            None => (version, None),
+        };
+
+        let mut numbers: Vec<u64> = Vec::new();
+        for s in numbers_part.split('.') {
+            match s.parse::<u64>() {
+                Ok(v) => numbers.push(v),
+                Err(_) => return Err(format!("版本号不正确：{}", version)),
+            };
+        }
+        let mut pre_release: Vec<String> = Vec::new();
+        if let Some(p) = pre_release_part {
+            for s in p.split('.') {
+                if s.is_empty() {
+                    return Err(format!("版本号不正确：{}", version));
+                }
+                pre_release.push(s.to_string());
+            }
+        }
+
+        return Ok(Version {
+            numbers,
+            pre_release,
+        });
+    }
+
+    pub fn is_pre_release(&self) -> bool {
+        return !self.pre_release.is_empty();
+    }
+}
+
+fn compare_pre_release_identifier(a: &str, b: &str) -> Ordering {
+    // 纯数字的标识按数值比较，且低于非数字标识
+    return match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(x), Ok(y)) => x.cmp(&y),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    };
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // 位数不同时缺少的部分按0处理，0.3 == 0.3.0
+        let len = self.numbers.len().max(other.numbers.len());
+        for i in 0..len {
+            let a = self.numbers.get(i).copied().unwrap_or(0);
+            let b = other.numbers.get(i).copied().unwrap_or(0);
+            match a.cmp(&b) {
+                Ordering::Equal => {}
+                v => return v,
+            }
+        }
+
+        match (self.is_pre_release(), other.is_pre_release()) {
+            (false, false) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+        for (a, b) in self.pre_release.iter().zip(other.pre_release.iter()) {
+            match compare_pre_release_identifier(a, b) {
+                Ordering::Equal => {}
+                v => return v,
+            }
+        }
+        return self.pre_release.len().cmp(&other.pre_release.len());
+    }
+}
+
+// 与cmp保持一致，0.3和0.3.0相等
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        return self.cmp(other) == Ordering::Equal;
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+pub fn compare_version(a: &str, b: &str) -> Result<Ordering, String> {
+    return Ok(Version::parse(a)?.cmp(&Version::parse(b)?));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: &str) -> Version {
+        return Version::parse(v).unwrap();
+    }
+
+    #[test]
+    fn eq_matches_cmp() {
+        let cases = [
+            ("1.0", "1.0.0", true),
+            ("v1.0.0", "1.0.0+build.5", true),
+            ("0.3.0-beta.1", "0.3-beta.1", true),
+            ("0.3.0-beta.1", "0.3.0", false),
+            ("0.3.0-beta", "0.3.0-beta.1", false),
+            ("1.0.1", "1.0", false),
+        ];
+        for (a, b, equal) in cases {
+            assert_eq!(version(a) == version(b), equal, "{} == {}", a, b);
+            assert_eq!(
+                version(a).cmp(&version(b)) == Ordering::Equal,
+                equal,
+                "{} cmp {}",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn semver_order() {
+        let ordered = [
+            "0.3.0-alpha",
+            "0.3.0-beta",
+            "0.3.0-beta.1",
+            "0.3.0-beta.2",
+            "0.3.0-beta.11",
+            "0.3.0",
+            "0.3.1",
+            "1.0",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(
+                version(pair[0]) < version(pair[1]),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+}
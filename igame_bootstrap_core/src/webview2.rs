@@ -0,0 +1,182 @@
+use std::fmt;
+
+use crate::registry::{Registry, RegistryRoot, RegistryView};
+use crate::version::{compare_version, Version};
+
+// 参考微软文档“检测是否安装了合适的WebView2运行时”
+const WEBVIEW2_CLIENT_PATH: &str =
+    r"SOFTWARE\Microsoft\EdgeUpdate\Clients\{F3017226-FE2A-4295-8BDF-00C3A9A7E4C5}";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WebView2Scope {
+    /// 64位系统上安装在32位注册表视图下
+    Machine32,
+    Machine64,
+    User,
+}
+
+impl fmt::Display for WebView2Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebView2Scope::Machine32 => write!(f, "machine-32"),
+            WebView2Scope::Machine64 => write!(f, "machine-64"),
+            WebView2Scope::User => write!(f, "user"),
+        }
+    }
+}
+
+pub struct WebView2Runtime {
+    pub scope: WebView2Scope,
+    pub version: String,
+}
+
+// 卸载后pv可能残留为0.0.0.0，无法解析或全为0的版本按未安装处理
+fn is_valid_version(version: &str) -> bool {
+    return match Version::parse(version) {
+        Ok(v) => !v.numbers.is_empty() && v.numbers.iter().any(|n| *n != 0),
+        Err(_) => false,
+    };
+}
+
+fn read_runtime_version(
+    registry: &dyn Registry,
+    root: RegistryRoot,
+    view: RegistryView,
+) -> Option<String> {
+    let version = registry.get_string_in_view(root, view, WEBVIEW2_CLIENT_PATH, "pv")?;
+
+    if !is_valid_version(&version) {
+        return None;
+    }
+    return Some(version.trim().to_string());
+}
+
+/// 依次检查所有安装位置，返回其中版本最高的运行时
+pub fn get_installed_runtime(registry: &dyn Registry) -> Option<WebView2Runtime> {
+    let candidates = [
+        (
+            WebView2Scope::Machine32,
+            RegistryRoot::LocalMachine,
+            RegistryView::Wow64_32,
+        ),
+        (
+            WebView2Scope::Machine64,
+            RegistryRoot::LocalMachine,
+            RegistryView::Wow64_64,
+        ),
+        (
+            WebView2Scope::User,
+            RegistryRoot::CurrentUser,
+            RegistryView::Default,
+        ),
+    ];
+
+    let mut runtime: Option<WebView2Runtime> = None;
+    for (scope, root, view) in candidates {
+        let version = match read_runtime_version(registry, root, view) {
+            Some(v) => v,
+            None => continue,
+        };
+        let is_newer = match &runtime {
+            Some(v) => matches!(
+                compare_version(&version, &v.version),
+                Ok(std::cmp::Ordering::Greater)
+            ),
+            None => true,
+        };
+        if is_newer {
+            runtime = Some(WebView2Runtime { scope, version });
+        }
+    }
+    return runtime;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::FakeRegistry;
+
+    fn detect(fixture: &str) -> Option<(WebView2Scope, String)> {
+        let registry = FakeRegistry::from_fixture(fixture).unwrap();
+        return get_installed_runtime(&registry).map(|v| (v.scope, v.version));
+    }
+
+    fn runtime(root: &str, version: &str) -> String {
+        return format!(
+            "[{}\\{}]\n\"pv\"=\"{}\"\n",
+            root, WEBVIEW2_CLIENT_PATH, version
+        );
+    }
+
+    #[test]
+    fn not_installed() {
+        assert_eq!(detect(""), None);
+        // 其他EdgeUpdate客户端不是WebView2
+        assert_eq!(
+            detect(
+                r#"[HKLM32\SOFTWARE\Microsoft\EdgeUpdate\Clients\{56EB18F8-B008-4CBD-B6D2-8C97FE7E9062}]
+                "pv"="109.0.1518.61""#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn detects_each_location() {
+        let cases = [
+            ("HKLM32", WebView2Scope::Machine32),
+            ("HKLM64", WebView2Scope::Machine64),
+            ("HKCU", WebView2Scope::User),
+        ];
+        for (root, scope) in cases {
+            assert_eq!(
+                detect(&runtime(root, "109.0.1518.61")),
+                Some((scope, "109.0.1518.61".to_string())),
+                "{}",
+                root
+            );
+        }
+        // 32位系统上没有视图之分，写在默认视图下
+        assert_eq!(
+            detect(&runtime("HKLM", "109.0.1518.61")),
+            Some((WebView2Scope::Machine32, "109.0.1518.61".to_string()))
+        );
+    }
+
+    #[test]
+    fn ignores_placeholder_versions() {
+        for version in ["0.0.0.0", "0", "", "not-a-version"] {
+            assert_eq!(detect(&runtime("HKLM32", version)), None, "{}", version);
+        }
+        // 残留的0.0.0.0不影响其他位置的运行时
+        let fixture = runtime("HKLM32", "0.0.0.0") + &runtime("HKCU", "100.0.1185.36");
+        assert_eq!(
+            detect(&fixture),
+            Some((WebView2Scope::User, "100.0.1185.36".to_string()))
+        );
+    }
+
+    #[test]
+    fn highest_version_wins() {
+        let fixture = runtime("HKLM32", "108.0.1462.76")
+            + &runtime("HKLM64", "110.0.1587.41")
+            + &runtime("HKCU", "109.0.1518.61");
+        assert_eq!(
+            detect(&fixture),
+            Some((WebView2Scope::Machine64, "110.0.1587.41".to_string()))
+        );
+
+        let fixture = runtime("HKLM32", "100.0.1185.36") + &runtime("HKCU", "100.0.1185.100");
+        assert_eq!(
+            detect(&fixture),
+            Some((WebView2Scope::User, "100.0.1185.100".to_string()))
+        );
+
+        // 版本相同时保留先检查到的位置
+        let fixture = runtime("HKLM32", "109.0.1518.61") + &runtime("HKCU", "109.0.1518.61");
+        assert_eq!(
+            detect(&fixture),
+            Some((WebView2Scope::Machine32, "109.0.1518.61".to_string()))
+        );
+    }
+}
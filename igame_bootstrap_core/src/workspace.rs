@@ -0,0 +1,473 @@
+use lazy_static::lazy_static;
+use rand::Rng;
+use std::fs::{self, create_dir_all, read_dir};
+#[cfg(windows)]
+use std::os::windows::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use crate::file::try_remove_path;
+use crate::platform::{SpecialFolders, SystemPlatform, Volumes};
+use crate::static_var;
+use crate::time::unix_timestamp;
+
+const WORKSPACE_ROOT_NAME: &str = "IGameBootstrapper";
+// %TEMP%空间不足时，在其他磁盘根目录下使用的目录名
+const ALTERNATIVE_ROOT_NAME: &str = "IGameBootstrapperTemp";
+const WORKSPACE_DIR_PREFIX: &str = "run-";
+const LOCK_FILE_NAME: &str = "run.lock";
+const META_FILE_NAME: &str = "run.meta";
+// 超过这个时间且已不在运行的工作目录会被清理
+const STALE_WORKSPACE_SECONDS: u64 = 6 * 60 * 60;
+// 旧版本直接放在%TEMP%下的固定文件名
+const LEGACY_TEMP_FILE_NAMES: [&str; 5] = [
+    "Rootsupd.tzst",
+    ".NET Framework 4.8.tzst",
+    "WebView2Installer.tzst",
+    "IGameInstaller.tzst",
+    "IGameBootstrapper.tzst",
+];
+
+struct Workspace {
+    dir_path: PathBuf,
+    // 持有锁文件句柄，进程退出后由系统释放
+    lock_file: fs::File,
+}
+
+lazy_static! {
+    static ref WORKSPACE: Mutex<Option<Workspace>> = Mutex::new(None);
+}
+
+fn random_string(len: usize) -> String {
+    return rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect();
+}
+
+pub fn get_workspace_root_path() -> PathBuf {
+    let mut root_path = SystemPlatform.temp_dir();
+    root_path.push(WORKSPACE_ROOT_NAME);
+    return root_path;
+}
+
+pub fn get_alternative_root_path(drive_root: &PathBuf) -> PathBuf {
+    let mut root_path = drive_root.clone();
+    root_path.push(ALTERNATIVE_ROOT_NAME);
+    return root_path;
+}
+
+fn create_workspace(root_path: &PathBuf) -> Result<Workspace, String> {
+    let timestamp = unix_timestamp();
+    let pid = std::process::id();
+    let mut dir_path = root_path.clone();
+    dir_path.push(format!(
+        "{}{}-{}-{}",
+        WORKSPACE_DIR_PREFIX,
+        timestamp,
+        pid,
+        random_string(4)
+    ));
+
+    match create_dir_all(&dir_path) {
+        Err(e) => {
+            return Err(format!("创建工作目录失败：{:?}\n{:?}", dir_path, e));
+        }
+        _ => {}
+    };
+
+    let mut lock_path = dir_path.clone();
+    lock_path.push(LOCK_FILE_NAME);
+    let mut lock_options = fs::File::options();
+    lock_options.write(true).create(true).truncate(true);
+    // 独占打开，其他进程无法删除锁文件；其他系统上只能依靠目录的修改时间判断
+    #[cfg(windows)]
+    lock_options.share_mode(0);
+    let lock_file = match lock_options.open(&lock_path) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(format!("创建锁文件失败：{:?}\n{:?}", lock_path, e));
+        }
+    };
+
+    let mut meta_path = dir_path.clone();
+    meta_path.push(META_FILE_NAME);
+    let meta = format!(
+        "pid={}\ntimestamp={}\nversion={}\n",
+        pid,
+        timestamp,
+        env!("CARGO_PKG_VERSION")
+    );
+    match fs::write(&meta_path, meta) {
+        Err(e) => {
+            return Err(format!("写入工作目录信息失败：{:?}\n{:?}", meta_path, e));
+        }
+        _ => {}
+    };
+
+    return Ok(Workspace {
+        dir_path,
+        lock_file,
+    });
+}
+
+pub fn try_init_workspace() -> Result<(), String> {
+    let mut workspace = WORKSPACE.lock().unwrap();
+    if workspace.is_none() {
+        *workspace = Some(create_workspace(&get_workspace_root_path())?);
+    }
+
+    return Ok(());
+}
+
+// 除了锁文件和元信息外还有其他文件，说明已经开始下载
+fn workspace_has_files(dir_path: &PathBuf) -> bool {
+    let entries = match read_dir(dir_path) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    return entries.flatten().any(|entry| {
+        let name = entry.file_name();
+        name != LOCK_FILE_NAME && name != META_FILE_NAME
+    });
+}
+
+fn relocate_workspace(
+    workspace: &mut Option<Workspace>,
+    root_path: &PathBuf,
+) -> Result<(), String> {
+    // 旧目录会被整个删除，里面不能有已下载的文件
+    if let Some(v) = workspace.as_ref() {
+        if workspace_has_files(&v.dir_path) {
+            return Err(format!("工作目录中已有文件，无法迁移：{:?}", v.dir_path));
+        }
+    }
+
+    let new_workspace = create_workspace(root_path)?;
+    if let Some(v) = workspace.replace(new_workspace) {
+        let dir_path = v.dir_path.clone();
+        drop(v.lock_file);
+        let _ = try_remove_path(&dir_path);
+    }
+
+    return Ok(());
+}
+
+/// 把工作目录迁移到新的根目录下，只能在开始下载之前调用，旧目录中已有文件时返回错误
+pub fn try_relocate_workspace(root_path: &PathBuf) -> Result<(), String> {
+    let mut workspace = WORKSPACE.lock().unwrap();
+    return relocate_workspace(&mut workspace, root_path);
+}
+
+pub fn get_workspace_dir_path() -> PathBuf {
+    {
+        let workspace = WORKSPACE.lock().unwrap();
+        if let Some(v) = workspace.as_ref() {
+            return v.dir_path.clone();
+        }
+    }
+
+    // 未初始化时退回到%TEMP%，保证调用方总能拿到可用路径
+    return SystemPlatform.temp_dir();
+}
+
+pub fn get_workspace_file_path(name: &str) -> PathBuf {
+    let mut file_path = get_workspace_dir_path();
+    file_path.push(name);
+    return file_path;
+}
+
+pub fn get_random_workspace_dir_path() -> PathBuf {
+    let mut dir_path = get_workspace_dir_path();
+    dir_path.push(random_string(8));
+    while dir_path.exists() {
+        dir_path.pop();
+        dir_path.push(random_string(8));
+    }
+    return dir_path;
+}
+
+pub fn write_workspace_file(name: &str) -> Result<(PathBuf, fs::File), std::io::Error> {
+    let file_path = get_workspace_file_path(name);
+    let file = fs::File::options()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(file_path.clone())?;
+    return Ok((file_path, file));
+}
+
+/// 退出时删除本次运行的工作目录
+pub fn try_remove_workspace() {
+    let mut workspace = match WORKSPACE.try_lock() {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    if let Some(v) = workspace.take() {
+        let dir_path = v.dir_path.clone();
+        drop(v.lock_file);
+        let _ = try_remove_path(&dir_path);
+    }
+}
+
+fn read_workspace_timestamp(dir_path: &PathBuf) -> Option<u64> {
+    let mut meta_path = dir_path.clone();
+    meta_path.push(META_FILE_NAME);
+    if let Ok(meta) = fs::read_to_string(&meta_path) {
+        for line in meta.lines() {
+            if let Some(v) = line.strip_prefix("timestamp=") {
+                return v.trim().parse::<u64>().ok();
+            }
+        }
+    }
+
+    // 没有元信息时按目录修改时间计算
+    return fs::metadata(dir_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+}
+
+fn workspace_is_alive(dir_path: &PathBuf) -> bool {
+    // 锁文件被独占打开，能删除说明对应的进程已经结束
+    let mut lock_path = dir_path.clone();
+    lock_path.push(LOCK_FILE_NAME);
+    if !lock_path.exists() {
+        return false;
+    }
+    return fs::remove_file(&lock_path).is_err();
+}
+
+fn file_is_stale(path: &PathBuf, now: u64) -> bool {
+    return match fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    {
+        Some(v) => now.saturating_sub(v.as_secs()) > STALE_WORKSPACE_SECONDS,
+        None => false,
+    };
+}
+
+fn clean_old_exe_files(self_dir_path: &PathBuf, now: u64) {
+    let entries = match read_dir(self_dir_path) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let stem = match file_name.strip_suffix("_old.exe") {
+            Some(v) => v.to_string(),
+            None => continue,
+        };
+        // 只删除能找到对应新版本的旧文件，避免误删用户文件
+        let mut new_exe_path = self_dir_path.clone();
+        new_exe_path.push(format!("{}.exe", stem));
+        // 有更新记录时由try_check_update_state处理，等待确认期间的旧版本用于还原
+        // 旧版本是改名得到的，修改时间仍是原来安装的时间，不能据此判断
+        let mut marker_path = self_dir_path.clone();
+        marker_path.push(format!("{}_update.marker", stem));
+        let old_exe_path = entry.path();
+        if new_exe_path.exists() && !marker_path.exists() && file_is_stale(&old_exe_path, now) {
+            let _ = try_remove_path(&old_exe_path);
+        }
+    }
+}
+
+fn clean_stale_workspaces_in(root_path: &PathBuf, own_dir_path: &Option<PathBuf>, now: u64) {
+    if let Ok(entries) = read_dir(root_path) {
+        for entry in entries.flatten() {
+            let dir_path = entry.path();
+            if !dir_path.is_dir()
+                || !entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(WORKSPACE_DIR_PREFIX)
+                || Some(&dir_path) == own_dir_path.as_ref()
+            {
+                continue;
+            }
+            let timestamp = match read_workspace_timestamp(&dir_path) {
+                Some(v) => v,
+                None => continue,
+            };
+            if now.saturating_sub(timestamp) <= STALE_WORKSPACE_SECONDS {
+                continue;
+            }
+            if workspace_is_alive(&dir_path) {
+                continue;
+            }
+            let _ = try_remove_path(&dir_path);
+        }
+    }
+}
+
+fn clean_legacy_temp_files(temp_dir: &PathBuf, now: u64) {
+    for name in LEGACY_TEMP_FILE_NAMES {
+        let mut legacy_path = temp_dir.clone();
+        legacy_path.push(name);
+        if file_is_stale(&legacy_path, now) {
+            let _ = try_remove_path(&legacy_path);
+        }
+    }
+}
+
+pub fn clean_stale_workspaces() {
+    let now = unix_timestamp();
+    let own_dir_path = {
+        let workspace = WORKSPACE.lock().unwrap();
+        workspace.as_ref().map(|v| v.dir_path.clone())
+    };
+
+    clean_stale_workspaces_in(&get_workspace_root_path(), &own_dir_path, now);
+    for drive_root in SystemPlatform.list_fixed_drive_roots() {
+        let root_path = get_alternative_root_path(&drive_root);
+        if root_path.exists() {
+            clean_stale_workspaces_in(&root_path, &own_dir_path, now);
+        }
+    }
+
+    clean_legacy_temp_files(&SystemPlatform.temp_dir(), now);
+
+    let self_exe_path = PathBuf::from((*static_var::CURRENT_EXE_PATH).clone());
+    if let Some(v) = self_exe_path.parent() {
+        clean_old_exe_files(&v.to_path_buf(), now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root_path = std::env::temp_dir().join(format!(
+            "igb-workspace-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&root_path);
+        create_dir_all(&root_path).unwrap();
+        return root_path;
+    }
+
+    // 创建工作目录并改写元信息中的时间，释放锁文件后返回目录
+    fn workspace_at(root_path: &PathBuf, timestamp: u64) -> PathBuf {
+        let workspace = create_workspace(root_path).unwrap();
+        let dir_path = workspace.dir_path.clone();
+        drop(workspace.lock_file);
+        fs::write(
+            dir_path.join(META_FILE_NAME),
+            format!("pid=1\ntimestamp={}\nversion=0.0.0\n", timestamp),
+        )
+        .unwrap();
+        return dir_path;
+    }
+
+    #[test]
+    fn stale_workspaces_are_removed() {
+        let root_path = temp_root("stale");
+        let now = 10 * STALE_WORKSPACE_SECONDS;
+        let stale = workspace_at(&root_path, now - STALE_WORKSPACE_SECONDS - 1);
+        let at_threshold = workspace_at(&root_path, now - STALE_WORKSPACE_SECONDS);
+        let recent = workspace_at(&root_path, now - 60);
+        let own = workspace_at(&root_path, 0);
+        // 不是run-开头的目录不处理
+        let other = root_path.join("other");
+        create_dir_all(&other).unwrap();
+        fs::write(other.join(META_FILE_NAME), "timestamp=0\n").unwrap();
+
+        clean_stale_workspaces_in(&root_path, &Some(own.clone()), now);
+
+        assert!(!stale.exists());
+        assert!(at_threshold.exists());
+        assert!(recent.exists());
+        assert!(own.exists());
+        assert!(other.exists());
+
+        // 不排除当前目录时，同样会被清理
+        clean_stale_workspaces_in(&root_path, &None, now);
+        assert!(!own.exists());
+        assert!(recent.exists());
+
+        let _ = fs::remove_dir_all(&root_path);
+    }
+
+    #[test]
+    fn legacy_temp_files_are_removed_when_stale() {
+        let temp_dir = temp_root("legacy");
+        for name in LEGACY_TEMP_FILE_NAMES {
+            fs::write(temp_dir.join(name), b"legacy").unwrap();
+        }
+        let unrelated = temp_dir.join("Other.tzst");
+        fs::write(&unrelated, b"other").unwrap();
+
+        let now = unix_timestamp();
+        clean_legacy_temp_files(&temp_dir, now);
+        for name in LEGACY_TEMP_FILE_NAMES {
+            assert!(temp_dir.join(name).exists(), "{}", name);
+        }
+
+        clean_legacy_temp_files(&temp_dir, now + STALE_WORKSPACE_SECONDS + 60);
+        for name in LEGACY_TEMP_FILE_NAMES {
+            assert!(!temp_dir.join(name).exists(), "{}", name);
+        }
+        assert!(unrelated.exists());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn old_exe_files_are_removed_when_replaced() {
+        let dir_path = temp_root("old-exe");
+        for name in [
+            "a.exe",
+            "a_old.exe",
+            "b_old.exe",
+            "c.exe",
+            "c_old.exe",
+            "c_update.marker",
+        ] {
+            fs::write(dir_path.join(name), b"").unwrap();
+        }
+
+        let later = unix_timestamp() + STALE_WORKSPACE_SECONDS + 60;
+        clean_old_exe_files(&dir_path, unix_timestamp());
+        assert!(dir_path.join("a_old.exe").exists());
+
+        clean_old_exe_files(&dir_path, later);
+        assert!(!dir_path.join("a_old.exe").exists());
+        // 没有对应的新版本
+        assert!(dir_path.join("b_old.exe").exists());
+        // 更新尚未确认
+        assert!(dir_path.join("c_old.exe").exists());
+
+        let _ = fs::remove_dir_all(&dir_path);
+    }
+
+    #[test]
+    fn relocate_only_empty_workspace() {
+        let old_root = temp_root("relocate-old");
+        let new_root = temp_root("relocate-new");
+        let mut workspace = Some(create_workspace(&old_root).unwrap());
+        let old_dir = workspace.as_ref().unwrap().dir_path.clone();
+
+        fs::write(old_dir.join("IGameInstaller.tzst"), b"downloaded").unwrap();
+        assert!(relocate_workspace(&mut workspace, &new_root).is_err());
+        assert_eq!(workspace.as_ref().unwrap().dir_path, old_dir);
+        assert!(old_dir.join("IGameInstaller.tzst").exists());
+
+        fs::remove_file(old_dir.join("IGameInstaller.tzst")).unwrap();
+        relocate_workspace(&mut workspace, &new_root).unwrap();
+        let new_dir = workspace.as_ref().unwrap().dir_path.clone();
+        assert!(new_dir.starts_with(&new_root));
+        assert!(new_dir.join(LOCK_FILE_NAME).exists());
+        assert!(!old_dir.exists());
+
+        drop(workspace);
+        let _ = fs::remove_dir_all(&old_root);
+        let _ = fs::remove_dir_all(&new_root);
+    }
+}
@@ -0,0 +1,347 @@
+use std::path::PathBuf;
+
+use igame_bootstrap_core::trailer::{
+    read_launch_payload, read_trailer, sign_launch_payload, strip_trailer,
+    write_signed_launch_payload, LaunchPayload, PayloadSignature,
+};
+
+const USAGE: &str = "用法：
+  igb-stamp read <exe>
+  igb-stamp verify <exe>
+  igb-stamp write <exe> <资源ID[,资源ID...]> --key <私钥文件> [--channel <通道>] [--attribution <标识>] [--arg <安装器参数>]...
+  igb-stamp replace <exe> <资源ID[,资源ID...]> [选项同write]
+  igb-stamp strip <exe>
+  igb-stamp batch <exe> <资源ID列表文件> <输出目录> [选项同write]
+私钥文件为PKCS#8格式的Ed25519私钥，可以是DER或base64文本";
+
+fn parse_resource_ids(value: &str) -> Result<Vec<i32>, String> {
+    let mut resource_ids: Vec<i32> = Vec::new();
+    for s in value.split(',') {
+        match s.trim().parse::<i32>() {
+            Ok(v) => resource_ids.push(v),
+            Err(_) => return Err(format!("资源ID不正确：{}", s)),
+        };
+    }
+    return Ok(resource_ids);
+}
+
+struct StampOptions {
+    payload: LaunchPayload,
+    key_path: PathBuf,
+}
+
+fn parse_options(resource_ids: Vec<i32>, options: &[String]) -> Result<StampOptions, String> {
+    let mut payload = LaunchPayload {
+        resource_ids,
+        ..Default::default()
+    };
+    let mut key_path: Option<PathBuf> = None;
+    let mut iter = options.iter();
+    while let Some(option) = iter.next() {
+        let value = match iter.next() {
+            Some(v) => v.clone(),
+            None => return Err(format!("选项缺少参数：{}", option)),
+        };
+        match option.as_str() {
+            "--channel" => payload.channel = Some(value),
+            "--attribution" => payload.attribution = Some(value),
+            "--arg" => payload.installer_args.push(value),
+            "--key" => key_path = Some(PathBuf::from(value)),
+            _ => return Err(format!("未知选项：{}", option)),
+        }
+    }
+    // 没有签名的启动信息在运行时会被忽略
+    let key_path = match key_path {
+        Some(v) => v,
+        None => return Err("缺少签名私钥：--key <私钥文件>".to_string()),
+    };
+    return Ok(StampOptions { payload, key_path });
+}
+
+fn read_private_key(path: &PathBuf) -> Result<Vec<u8>, String> {
+    let key = match std::fs::read(path) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("读取签名私钥失败：{:?}\n{:?}", path, e)),
+    };
+    match base64::decode(String::from_utf8_lossy(&key).trim()) {
+        Ok(v) => return Ok(v),
+        Err(_) => return Ok(key),
+    }
+}
+
+// 用运行时同一套读取逻辑检查写入结果，签名必须能被内置的公钥验证
+fn verify(path: &PathBuf, expected: Option<&LaunchPayload>) -> Result<(), String> {
+    let signed_payload = read_launch_payload(path)?;
+    match signed_payload.signature {
+        PayloadSignature::Valid => {}
+        PayloadSignature::Missing => return Err("启动信息缺少签名".to_string()),
+        PayloadSignature::Invalid => {
+            return Err("启动信息签名无效，私钥与内置的公钥不匹配".to_string())
+        }
+    }
+    if let Some(v) = expected {
+        if signed_payload.payload != *v {
+            return Err(format!(
+                "写入后读取的内容不一致：{:?}\n{:?}",
+                signed_payload.payload, v
+            ));
+        }
+    }
+    return Ok(());
+}
+
+fn print_trailer(path: &PathBuf) -> Result<(), String> {
+    let signed_payload = read_launch_payload(path)?;
+    let version = match read_trailer(path) {
+        Ok(v) => v.version.to_string(),
+        Err(_) => "旧格式".to_string(),
+    };
+    let signature = match signed_payload.signature {
+        PayloadSignature::Valid => "有效",
+        PayloadSignature::Missing => "无签名",
+        PayloadSignature::Invalid => "无效",
+    };
+    println!("格式版本：{}", version);
+    println!("签名：{}", signature);
+    println!(
+        "内容：{}",
+        serde_json::to_string_pretty(&signed_payload.payload).unwrap()
+    );
+    return Ok(());
+}
+
+fn stamp(path: &PathBuf, payload: &LaunchPayload, private_key: &[u8]) -> Result<(), String> {
+    let (data, signature) = sign_launch_payload(payload, private_key)?;
+    write_signed_launch_payload(path, &data, &signature)?;
+    verify(path, Some(payload))?;
+    println!("已写入：{:?} {:?}", path, payload.resource_ids);
+    return Ok(());
+}
+
+// 输出文件名为原文件名加上各资源ID，如IGameBootstrapper_13_9.exe
+fn batch_file_name(stem: &str, resource_ids: &[i32]) -> String {
+    let ids: Vec<String> = resource_ids.iter().map(|v| v.to_string()).collect();
+    return format!("{}_{}.exe", stem, ids.join("_"));
+}
+
+fn batch(
+    src_path: &PathBuf,
+    list_path: &PathBuf,
+    out_dir: &PathBuf,
+    options: &[String],
+) -> Result<(), String> {
+    let list = match std::fs::read_to_string(list_path) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("读取资源ID列表失败：{:?}\n{:?}", list_path, e)),
+    };
+    match std::fs::create_dir_all(out_dir) {
+        Err(e) => return Err(format!("创建文件夹失败：{:?}\n{:?}", out_dir, e)),
+        _ => {}
+    };
+    let stem = match src_path.file_stem() {
+        Some(v) => v.to_string_lossy().to_string(),
+        None => return Err(format!("文件名不正确：{:?}", src_path)),
+    };
+    let key_path = parse_options(Vec::new(), options)?.key_path;
+    let private_key = read_private_key(&key_path)?;
+
+    // 每行一组资源ID，逗号分隔，#开头的行为注释
+    for line in list.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let payload = parse_options(parse_resource_ids(line)?, options)?.payload;
+        let mut dst_path = out_dir.clone();
+        dst_path.push(batch_file_name(&stem, &payload.resource_ids));
+        match std::fs::copy(src_path, &dst_path) {
+            Err(e) => {
+                return Err(format!(
+                    "复制文件失败：{:?} -> {:?}\n{:?}",
+                    src_path, dst_path, e
+                ))
+            }
+            _ => {}
+        };
+        stamp(&dst_path, &payload, &private_key)?;
+    }
+    return Ok(());
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    if args.len() < 2 {
+        return Err(USAGE.to_string());
+    }
+    let path = PathBuf::from(&args[1]);
+
+    match (args[0].as_str(), args.len()) {
+        ("read", 2) => return print_trailer(&path),
+        ("verify", 2) => {
+            verify(&path, None)?;
+            println!("校验通过");
+            return Ok(());
+        }
+        ("write", n) if n >= 3 => {
+            let options = parse_options(parse_resource_ids(&args[2])?, &args[3..])?;
+            return stamp(
+                &path,
+                &options.payload,
+                &read_private_key(&options.key_path)?,
+            );
+        }
+        ("replace", n) if n >= 3 => {
+            read_launch_payload(&path).map_err(|e| format!("文件中没有可替换的内容：{}", e))?;
+            let options = parse_options(parse_resource_ids(&args[2])?, &args[3..])?;
+            return stamp(
+                &path,
+                &options.payload,
+                &read_private_key(&options.key_path)?,
+            );
+        }
+        ("strip", 2) => {
+            strip_trailer(&path)?;
+            println!("已去除：{:?}", path);
+            return Ok(());
+        }
+        ("batch", n) if n >= 4 => {
+            return batch(
+                &path,
+                &PathBuf::from(&args[2]),
+                &PathBuf::from(&args[3]),
+                &args[4..],
+            );
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        _ => {}
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        return args.iter().map(|v| v.to_string()).collect();
+    }
+
+    #[test]
+    fn resource_ids() {
+        assert_eq!(parse_resource_ids("13").unwrap(), vec![13]);
+        assert_eq!(parse_resource_ids("13,9").unwrap(), vec![13, 9]);
+        assert_eq!(parse_resource_ids(" 13 , 9 ").unwrap(), vec![13, 9]);
+        for value in ["", "13,", "a", "13;9", "99999999999"] {
+            assert!(parse_resource_ids(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn options() {
+        let options = parse_options(
+            vec![13],
+            &strings(&[
+                "--channel",
+                "beta",
+                "--key",
+                "key.der",
+                "--attribution",
+                "ad-1",
+                "--arg",
+                "--silent",
+                "--arg",
+                "--lang=zh",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(options.key_path, PathBuf::from("key.der"));
+        assert_eq!(
+            options.payload,
+            LaunchPayload {
+                resource_ids: vec![13],
+                channel: Some("beta".to_string()),
+                attribution: Some("ad-1".to_string()),
+                installer_args: strings(&["--silent", "--lang=zh"]),
+            }
+        );
+
+        let minimal = parse_options(vec![13], &strings(&["--key", "key.der"])).unwrap();
+        assert_eq!(minimal.payload, LaunchPayload::from_resource_id(13));
+    }
+
+    #[test]
+    fn invalid_options() {
+        let cases: [&[&str]; 4] = [
+            &[],
+            &["--channel", "beta"],
+            &["--key", "key.der", "--channel"],
+            &["--key", "key.der", "--unknown", "1"],
+        ];
+        for options in cases {
+            assert!(
+                parse_options(vec![13], &strings(options)).is_err(),
+                "{:?}",
+                options
+            );
+        }
+    }
+
+    #[test]
+    fn usage_errors() {
+        let cases: [&[&str]; 6] = [
+            &[],
+            &["read"],
+            &["read", "a.exe", "b.exe"],
+            &["write", "a.exe"],
+            &["batch", "a.exe", "ids.txt"],
+            &["sign", "a.exe"],
+        ];
+        for args in cases {
+            assert_eq!(run(&strings(args)), Err(USAGE.to_string()), "{:?}", args);
+        }
+        // 参数错误在读取文件之前就会返回
+        assert!(run(&strings(&["write", "a.exe", "x"]))
+            .unwrap_err()
+            .starts_with("资源ID不正确"));
+        assert!(run(&strings(&["write", "a.exe", "13"]))
+            .unwrap_err()
+            .starts_with("缺少签名私钥"));
+    }
+
+    #[test]
+    fn batch_file_names() {
+        assert_eq!(
+            batch_file_name("IGameBootstrapper", &[13]),
+            "IGameBootstrapper_13.exe"
+        );
+        assert_eq!(
+            batch_file_name("IGameBootstrapper", &[13, 9]),
+            "IGameBootstrapper_13_9.exe"
+        );
+    }
+
+    #[test]
+    fn private_key_formats() {
+        let key: Vec<u8> = (0..83u8).collect();
+        let der_path =
+            std::env::temp_dir().join(format!("igb-stamp-test-{}-key.der", std::process::id()));
+        let text_path =
+            std::env::temp_dir().join(format!("igb-stamp-test-{}-key.txt", std::process::id()));
+        std::fs::write(&der_path, &key).unwrap();
+        std::fs::write(&text_path, format!("{}\n", base64::encode(&key))).unwrap();
+
+        assert_eq!(read_private_key(&der_path).unwrap(), key);
+        assert_eq!(read_private_key(&text_path).unwrap(), key);
+        let _ = std::fs::remove_file(&der_path);
+        let _ = std::fs::remove_file(&text_path);
+        assert!(read_private_key(&der_path).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use native_windows_gui as nwg;
+use nwg::NativeUi;
+
+use crate::ui::try_build_font;
+use crate::ui::{MainDlg, PromptDlg, UpdateDlg, UpdatePromptDlg};
+use igame_bootstrap_core::continuation::take_continuation;
+use igame_bootstrap_core::depend;
+use igame_bootstrap_core::dry_run::{
+    build_dry_run_report, get_dry_run_format, render_dry_run_report,
+};
+use igame_bootstrap_core::error::process_error;
+use igame_bootstrap_core::file::try_search_launch_payload;
+use igame_bootstrap_core::plan::{compute_install_plan, resolve_install_plan, InstallPolicy};
+use igame_bootstrap_core::platform::{attach_console, system_registry};
+use igame_bootstrap_core::process::{exit, start_igame_installer};
+use igame_bootstrap_core::static_var;
+use igame_bootstrap_core::system_info::os_is_ok;
+use igame_bootstrap_core::update::{
+    check_update, confirm_update_healthy, remind_update_later, skip_update_version,
+    try_check_update_state, UpdateChoice, UpdateRequirement,
+};
+use igame_bootstrap_core::workspace::{clean_stale_workspaces, try_init_workspace};
+
+/// 启动流程的选项
+#[derive(Default)]
+pub struct LaunchOptions {
+    /// 所有依赖都按不满足要求处理，并且总是显示提示，用于调试安装流程
+    pub install_all_depends: bool,
+}
+
+/// 引导器的完整启动流程：检查系统和更新，安装缺少的依赖，最后启动IGame安装器
+///
+/// 带`--dry-run`参数时只输出安装计划后退出
+pub fn run(options: &LaunchOptions) {
+    // 只输出安装计划，不下载也不安装
+    if let Some(format) = get_dry_run_format() {
+        attach_console();
+        match build_dry_run_report() {
+            Ok(v) => println!("{}", render_dry_run_report(&v, format)),
+            Err(e) => {
+                eprintln!("生成安装计划失败：{}", e);
+                exit(1);
+            }
+        };
+        exit(0);
+    }
+
+    // 检查系统是否满足要求
+    if !os_is_ok(system_registry()) {
+        process_error(
+            "本软件只能运行在Win7 Win8.1 Win10 Win11系统上\n请尝试升级你的Windows系统".to_string(),
+            true,
+            true,
+            false,
+            true,
+        );
+    }
+
+    // 更新后重启时沿用之前的启动信息和日志会话，否则读取自身末尾的启动信息
+    let continuation = take_continuation();
+    let launch_payload = match &continuation {
+        Some(v) => {
+            *static_var::LOG_SESSION.write().unwrap() = v.log_session.clone();
+            v.launch_payload.clone()
+        }
+        None => try_search_launch_payload(),
+    };
+    *static_var::LAUNCH_PAYLOAD.write().unwrap() = launch_payload.clone();
+
+    // 检查上次更新的状态，删除过时的文件
+    match try_check_update_state() {
+        Err(e) => process_error(format!("检查更新状态失败：{}", e), true, true, true, true),
+        _ => {}
+    };
+
+    // 创建本次运行的工作目录，并清理之前残留的工作目录
+    match try_init_workspace() {
+        Err(e) => process_error(format!("初始化工作目录失败：{}", e), true, true, true, true),
+        _ => {}
+    };
+    clean_stale_workspaces();
+
+    // 检查更新，低于最低支持版本时必须更新，否则由用户选择
+    let update_requirement = if continuation.is_some() {
+        UpdateRequirement::NotNeeded
+    } else {
+        check_update()
+    };
+    let mut start_update = false;
+    if !matches!(update_requirement, UpdateRequirement::NotNeeded) {
+        match nwg::init() {
+            Err(e) => process_error(format!("初始化nwg失败：{}", e), true, true, true, true),
+            _ => {}
+        };
+        let mut default_font = nwg::Font::default();
+        try_build_font(15, "NSimSun", &mut default_font);
+        nwg::Font::set_global_default(Some(default_font));
+    }
+    match update_requirement {
+        UpdateRequirement::Mandatory(_) => start_update = true,
+        UpdateRequirement::Optional(update_info) => {
+            let mut update_prompt_dlg: UpdatePromptDlg = Default::default();
+            update_prompt_dlg.set_update_info(&update_info);
+            let update_prompt_dlg_ui = match UpdatePromptDlg::build_ui(update_prompt_dlg) {
+                Ok(v) => v,
+                Err(e) => {
+                    process_error(format!("初始化UI失败：{}", e), true, true, true, true);
+                    return;
+                }
+            };
+            nwg::dispatch_thread_events();
+            match update_prompt_dlg_ui.choice() {
+                UpdateChoice::UpdateNow => start_update = true,
+                UpdateChoice::SkipVersion => skip_update_version(&update_info.latest_version),
+                UpdateChoice::RemindLater => remind_update_later(),
+            };
+            drop(update_prompt_dlg_ui);
+        }
+        UpdateRequirement::NotNeeded => {}
+    };
+    if start_update {
+        let update_dlg: UpdateDlg = Default::default();
+        let _update_dlg_ui = match UpdateDlg::build_ui(update_dlg) {
+            Ok(v) => v,
+            Err(e) => {
+                process_error(format!("初始化UI失败：{}", e), true, true, true, true);
+                return;
+            }
+        };
+        nwg::dispatch_thread_events();
+    }
+
+    // 检查依赖并生成安装计划
+    depend::recover_igame_installer();
+    let mut detections = depend::detect_depends(system_registry(), false);
+    if options.install_all_depends {
+        for detection in detections.iter_mut() {
+            detection.status.satisfied = false;
+        }
+    }
+    let mut install_plan =
+        match compute_install_plan(&detections, *static_var::OS_ARCH, &InstallPolicy::default()) {
+            Ok(v) => v,
+            Err(e) => {
+                process_error(format!("生成安装计划失败：{}", e), true, true, true, true);
+                return;
+            }
+        };
+
+    // 需要安装依赖
+    if !install_plan.is_empty() {
+        // 下载之前检查磁盘空间
+        resolve_install_plan(&mut install_plan);
+        match depend::check_depends_space(&install_plan) {
+            Err(e) => process_error(format!("磁盘空间检查失败：{}", e), true, true, false, true),
+            _ => {}
+        };
+
+        match nwg::init() {
+            Ok(_) => {}
+            Err(e) => process_error(format!("初始化nwg失败：{}", e), true, true, true, true),
+        };
+        let mut default_font = nwg::Font::default();
+        try_build_font(18, "NSimSun", &mut default_font);
+        nwg::Font::set_global_default(Some(default_font));
+
+        // 界面可以正常初始化，确认更新后的新版本运行正常
+        // 安装依赖可能超过等待确认的时间，需要在显示界面之前确认
+        confirm_update_healthy();
+
+        // 需要安装IGame安装器以外的依赖
+        if install_plan.needs_prompt() || options.install_all_depends {
+            let mut prompt_dlg: PromptDlg = Default::default();
+            prompt_dlg.set_install_plan(&install_plan);
+            let prompt_dlg_ui = match PromptDlg::build_ui(prompt_dlg) {
+                Ok(v) => v,
+                Err(e) => {
+                    process_error(format!("初始化UI失败：{}", e), true, true, true, true);
+                    return;
+                }
+            };
+            nwg::dispatch_thread_events();
+            drop(prompt_dlg_ui);
+        }
+
+        let mut main_dlg: MainDlg = Default::default();
+        main_dlg.set_install_plan(&install_plan);
+        let _main_dlg_ui = match MainDlg::build_ui(main_dlg) {
+            Ok(v) => v,
+            Err(e) => {
+                process_error(format!("初始化UI失败：{}", e), true, true, true, true);
+                return;
+            }
+        };
+        nwg::dispatch_thread_events();
+    }
+
+    // 不需要安装依赖时没有界面，运行到这里同样说明新版本运行正常
+    confirm_update_healthy();
+
+    // 启动IGame安装器
+    start_igame_installer(&launch_payload);
+    exit(0);
+}
@@ -0,0 +1,10 @@
+//! IGame引导器的界面和启动流程，引导器本身和examples/debug.rs共用
+//!
+//! 与界面无关的逻辑都在igame_bootstrap_core中，这里只包含依赖native-windows-gui的部分，只支持Windows
+
+#![allow(non_snake_case)]
+
+#[cfg(windows)]
+pub mod launcher;
+#[cfg(windows)]
+pub mod ui;
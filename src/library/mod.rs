@@ -1,12 +0,0 @@
-pub mod api;
-pub mod crypto;
-pub mod depend;
-pub mod error;
-pub mod file;
-pub mod net;
-pub mod process;
-pub mod system_info;
-pub mod time;
-pub mod ui;
-pub mod update;
-pub mod window;
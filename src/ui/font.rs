@@ -1,6 +1,6 @@
 use native_windows_gui as nwg;
 
-use crate::library::error::process_error;
+use igame_bootstrap_core::error::process_error;
 
 pub fn try_build_font(size: u32, family: &str, font: &mut nwg::Font) {
     let result = nwg::Font::builder()
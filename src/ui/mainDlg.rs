@@ -4,10 +4,10 @@ use std::ops::Deref;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
-use crate::library::depend::{download_depend, install_depend};
-use crate::library::error::process_error;
-use crate::library::process::exit;
-use crate::library::ui::try_build_font;
+use crate::ui::{try_build_font, NoticeNotifier};
+use igame_bootstrap_core::error::process_error;
+use igame_bootstrap_core::plan::{DependPlan, InstallPlan};
+use igame_bootstrap_core::process::exit;
 
 #[derive(Default)]
 pub struct MainDlg {
@@ -32,15 +32,15 @@ pub struct MainDlg {
     start_install_notice: nwg::Notice,
     install_done_depend: Arc<Mutex<String>>,
     install_done_notice: nwg::Notice,
-    needed_depends: Vec<String>,
+    depends: Arc<Vec<DependPlan>>,
     installing_depends: Arc<Mutex<Vec<String>>>,
     installed_depends: Arc<Mutex<Vec<String>>>,
     is_error: Arc<Mutex<bool>>,
 }
 
 impl MainDlg {
-    pub fn set_needed_depends(&mut self, depends: &Vec<&str>) {
-        self.needed_depends = depends.iter().map(|s| s.to_string()).collect();
+    pub fn set_install_plan(&mut self, install_plan: &InstallPlan) {
+        self.depends = Arc::new(install_plan.depends.clone());
     }
 
     fn close(&self) {
@@ -210,8 +210,16 @@ impl nwg::NativeUi<MainDlgUi> for MainDlg {
                             }
                             install_description_sender.notice();
 
+                            let depends = dialog.depends.clone();
                             std::thread::spawn(move || {
-                                match install_depend(start_install_depend.as_str()) {
+                                let result = match depends
+                                    .iter()
+                                    .find(|v| v.name == start_install_depend)
+                                {
+                                    Some(v) => v.install(),
+                                    None => Err(format!("安装计划中没有{}", start_install_depend)),
+                                };
+                                match result {
                                     Ok(_) => {
                                         *install_done_depend.lock().unwrap() = start_install_depend;
                                         install_done_sender.notice();
@@ -251,7 +259,7 @@ impl nwg::NativeUi<MainDlgUi> for MainDlg {
                                 depends.push(install_done_depend);
                                 installed_depends_temp = depends.clone();
                             }
-                            if installed_depends_temp.len() == dialog.needed_depends.len() {
+                            if installed_depends_temp.len() == dialog.depends.len() {
                                 dialog.close();
                             } else if installing_depends_temp.len() == 0 {
                                 dialog.install_progressbar.set_pos(0);
@@ -280,7 +288,7 @@ impl nwg::NativeUi<MainDlgUi> for MainDlg {
                         }
                     }
                     E::OnInit => {
-                        let needed_depends = dialog.needed_depends.clone();
+                        let depends = dialog.depends.clone();
                         let download_description = dialog.download_description.clone();
                         let download_description_sender =
                             dialog.download_description_notice.sender();
@@ -290,7 +298,7 @@ impl nwg::NativeUi<MainDlgUi> for MainDlg {
                         let start_install_sender = dialog.start_install_notice.sender();
                         let is_error = dialog.is_error.clone();
                         std::thread::spawn(move || {
-                            for (i, id) in needed_depends.iter().enumerate() {
+                            for (i, depend) in depends.iter().enumerate() {
                                 if { *is_error.lock().unwrap() } == true {
                                     return;
                                 }
@@ -298,8 +306,8 @@ impl nwg::NativeUi<MainDlgUi> for MainDlg {
                                     *download_description.lock().unwrap() = format!(
                                         "（{}/{}）正在下载运行环境：{}",
                                         i + 1,
-                                        needed_depends.len(),
-                                        id
+                                        depends.len(),
+                                        depend.name
                                     )
                                 }
                                 download_description_sender.notice();
@@ -309,9 +317,11 @@ impl nwg::NativeUi<MainDlgUi> for MainDlg {
                                 }
                                 download_rate_sender.notice();
 
-                                match download_depend(id, c_download_rate, download_rate_sender) {
+                                match depend
+                                    .download(c_download_rate, NoticeNotifier(download_rate_sender))
+                                {
                                     Ok(_) => {
-                                        *start_install_depend.lock().unwrap() = id.to_string();
+                                        *start_install_depend.lock().unwrap() = depend.name.clone();
                                         start_install_sender.notice();
                                     }
                                     Err(e) => {
@@ -330,8 +340,8 @@ impl nwg::NativeUi<MainDlgUi> for MainDlg {
                             }
                             *download_description.lock().unwrap() = format!(
                                 "({}/{}) 所有下载已完成，请等待安装完成...",
-                                needed_depends.len(),
-                                needed_depends.len()
+                                depends.len(),
+                                depends.len()
                             );
                             download_description_sender.notice();
                         });
@@ -1,7 +1,13 @@
+mod font;
 mod mainDlg;
+mod notifier;
 mod promptDlg;
 mod updateDlg;
+mod updatePromptDlg;
 
+pub use font::try_build_font;
 pub use mainDlg::{MainDlg, MainDlgUi};
+pub use notifier::NoticeNotifier;
 pub use promptDlg::{PromptDlg, PromptDlgUi};
 pub use updateDlg::{UpdateDlg, UpdateDlgUi};
+pub use updatePromptDlg::{UpdatePromptDlg, UpdatePromptDlgUi};
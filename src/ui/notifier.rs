@@ -0,0 +1,12 @@
+use igame_bootstrap_core::platform::ProgressNotifier;
+use native_windows_gui as nwg;
+
+// 下载线程通过nwg的通知刷新界面上的进度
+#[derive(Clone, Copy)]
+pub struct NoticeNotifier(pub nwg::NoticeSender);
+
+impl ProgressNotifier for NoticeNotifier {
+    fn notify(&self) {
+        self.0.notice();
+    }
+}
@@ -4,8 +4,9 @@ use std::ops::Deref;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
-use crate::library::process::exit;
-use crate::library::ui::try_build_font;
+use crate::ui::try_build_font;
+use igame_bootstrap_core::plan::InstallPlan;
+use igame_bootstrap_core::process::exit;
 
 #[derive(Default)]
 pub struct PromptDlg {
@@ -28,8 +29,8 @@ pub struct PromptDlg {
 }
 
 impl PromptDlg {
-    pub fn set_needed_depends(&mut self, depends: &Vec<&str>) {
-        self.needed_depends = depends.iter().map(|s| s.to_string()).collect();
+    pub fn set_install_plan(&mut self, install_plan: &InstallPlan) {
+        self.needed_depends = install_plan.depends.iter().map(|v| v.describe()).collect();
     }
 
     fn close(&self) {
@@ -83,7 +84,7 @@ impl nwg::NativeUi<PromptDlgUi> for PromptDlg {
         nwg::Label::builder()
             .text(&format!(
                 "将要安装的运行环境有：{}",
-                dialog.needed_depends.join("，")
+                dialog.needed_depends.join("；")
             ))
             .position((20, 50))
             .size((560, 20))
@@ -4,10 +4,10 @@ use std::ops::Deref;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
-use crate::library::error::process_error;
-use crate::library::process::exit;
-use crate::library::ui::try_build_font;
-use crate::library::update::{download_update, install_update};
+use crate::ui::{try_build_font, NoticeNotifier};
+use igame_bootstrap_core::error::process_error;
+use igame_bootstrap_core::process::exit;
+use igame_bootstrap_core::update::{download_update, install_update};
 
 #[derive(Default)]
 pub struct UpdateDlg {
@@ -28,15 +28,9 @@ pub struct UpdateDlg {
     download_rate_notice: nwg::Notice,
     install_description: Arc<Mutex<String>>,
     install_description_notice: nwg::Notice,
-
-    resource_id: Arc<Mutex<i32>>,
 }
 
 impl UpdateDlg {
-    pub fn set_resource_id(&mut self, resource_id: i32) {
-        self.resource_id = Arc::new(Mutex::new(resource_id));
-    }
-
     // fn close(&self) {
     //     nwg::stop_thread_dispatch();
     // }
@@ -187,7 +181,6 @@ impl nwg::NativeUi<UpdateDlgUi> for UpdateDlg {
                         let download_rate_sender = dialog.download_rate_notice.sender();
                         let install_description = dialog.install_description.clone();
                         let install_description_sender = dialog.install_description_notice.sender();
-                        let resource_id = dialog.resource_id.clone();
                         std::thread::spawn(move || {
                             {
                                 *download_description.lock().unwrap() =
@@ -199,7 +192,10 @@ impl nwg::NativeUi<UpdateDlgUi> for UpdateDlg {
                             }
                             download_rate_sender.notice();
 
-                            match download_update(download_rate, download_rate_sender) {
+                            match download_update(
+                                download_rate,
+                                NoticeNotifier(download_rate_sender),
+                            ) {
                                 Err(e) => {
                                     process_error(
                                         format!("下载更新文件失败\n{}", e),
@@ -220,11 +216,7 @@ impl nwg::NativeUi<UpdateDlgUi> for UpdateDlg {
                                     "正在安装新版本...".to_string();
                             }
                             install_description_sender.notice();
-                            let c_resource_id;
-                            {
-                                c_resource_id = (*resource_id).lock().unwrap().clone();
-                            }
-                            match install_update(c_resource_id) {
+                            match install_update() {
                                 Err(e) => {
                                     process_error(
                                         format!("安装更新文件失败\n{}", e),
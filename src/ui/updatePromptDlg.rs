@@ -0,0 +1,184 @@
+use native_windows_gui as nwg;
+use std::cell::{Cell, RefCell};
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::ui::try_build_font;
+use igame_bootstrap_core::api::UpdateInfo;
+use igame_bootstrap_core::update::UpdateChoice;
+
+#[derive(Default)]
+pub struct UpdatePromptDlg {
+    window: nwg::Window,
+    window_icon: nwg::Icon,
+    embed_resource: nwg::EmbedResource,
+
+    prompt_label: nwg::Label,
+    release_notes_box: nwg::TextBox,
+    update_button: nwg::Button,
+    skip_button: nwg::Button,
+    later_button: nwg::Button,
+
+    latest_version: String,
+    release_notes: String,
+    choice: Cell<UpdateChoice>,
+}
+
+impl UpdatePromptDlg {
+    pub fn set_update_info(&mut self, update_info: &UpdateInfo) {
+        self.latest_version = update_info.latest_version.clone();
+        self.release_notes = update_info.release_notes.clone();
+    }
+
+    pub fn choice(&self) -> UpdateChoice {
+        return self.choice.get();
+    }
+
+    fn close(&self, choice: UpdateChoice) {
+        self.choice.set(choice);
+        self.window.set_visible(false);
+        nwg::stop_thread_dispatch();
+    }
+}
+
+pub struct UpdatePromptDlgUi {
+    inner: Rc<UpdatePromptDlg>,
+    default_handler: RefCell<Option<nwg::EventHandler>>,
+}
+
+impl nwg::NativeUi<UpdatePromptDlgUi> for UpdatePromptDlg {
+    fn build_ui(mut dialog: Self) -> Result<UpdatePromptDlgUi, nwg::NwgError> {
+        use nwg::Event as E;
+
+        let window_width = 500;
+        let window_height = 300;
+
+        // Resources
+        nwg::EmbedResource::builder().build(&mut dialog.embed_resource)?;
+
+        nwg::Icon::builder()
+            .source_embed(Some(&dialog.embed_resource))
+            .source_embed_str(Some("LOGO"))
+            .build(&mut dialog.window_icon)?;
+
+        // Controls
+        nwg::Window::builder()
+            .flags(
+                nwg::WindowFlags::WINDOW
+                    | nwg::WindowFlags::MINIMIZE_BOX
+                    | nwg::WindowFlags::VISIBLE,
+            )
+            .size((window_width, window_height))
+            .title(&format!("IGame引导器 v{}", env!("CARGO_PKG_VERSION")))
+            .center(true)
+            .icon(Some(&dialog.window_icon))
+            .build(&mut dialog.window)?;
+
+        let mut prompt_font = nwg::Font::default();
+        try_build_font(17, "NSimSun", &mut prompt_font);
+        nwg::Label::builder()
+            .text(&format!(
+                "检测到新版本 v{}，是否现在更新？",
+                dialog.latest_version
+            ))
+            .position((10, 10))
+            .size((480, 18))
+            .h_align(nwg::HTextAlign::Center)
+            .font(Some(&prompt_font))
+            .parent(&dialog.window)
+            .build(&mut dialog.prompt_label)?;
+
+        // 编辑框需要\r\n换行
+        let release_notes = if dialog.release_notes.is_empty() {
+            "暂无更新说明".to_string()
+        } else {
+            dialog
+                .release_notes
+                .replace("\r\n", "\n")
+                .replace('\n', "\r\n")
+        };
+        nwg::TextBox::builder()
+            .flags(nwg::TextBoxFlags::VISIBLE | nwg::TextBoxFlags::VSCROLL)
+            .readonly(true)
+            .text(&release_notes)
+            .position((10, 40))
+            .size((480, 190))
+            .parent(&dialog.window)
+            .build(&mut dialog.release_notes_box)?;
+
+        nwg::Button::builder()
+            .text("立即更新")
+            .position((10, 245))
+            .size((150, 40))
+            .parent(&dialog.window)
+            .build(&mut dialog.update_button)?;
+        nwg::Button::builder()
+            .text("跳过此版本")
+            .position((175, 245))
+            .size((150, 40))
+            .parent(&dialog.window)
+            .build(&mut dialog.skip_button)?;
+        nwg::Button::builder()
+            .text("稍后提醒")
+            .position((340, 245))
+            .size((150, 40))
+            .parent(&dialog.window)
+            .build(&mut dialog.later_button)?;
+
+        let ui = UpdatePromptDlgUi {
+            inner: Rc::new(dialog),
+            default_handler: Default::default(),
+        };
+
+        let event_ui = Rc::downgrade(&ui.inner);
+        let handle_events = move |event, _event_data, handle| {
+            if let Some(dialog) = event_ui.upgrade() {
+                match event {
+                    // 可选更新关闭窗口时按稍后提醒处理，继续启动
+                    E::OnWindowClose => {
+                        if &handle == &dialog.window {
+                            dialog.close(UpdateChoice::RemindLater);
+                        }
+                    }
+                    E::OnButtonClick => {
+                        if &handle == &dialog.update_button {
+                            dialog.close(UpdateChoice::UpdateNow);
+                        } else if &handle == &dialog.skip_button {
+                            dialog.close(UpdateChoice::SkipVersion);
+                        } else if &handle == &dialog.later_button {
+                            dialog.close(UpdateChoice::RemindLater);
+                        }
+                    }
+                    E::OnInit => {
+                        dialog.update_button.set_focus();
+                    }
+                    _ => {}
+                }
+            }
+        };
+
+        *ui.default_handler.borrow_mut() = Some(nwg::full_bind_event_handler(
+            &ui.inner.window.handle,
+            handle_events,
+        ));
+
+        return Ok(ui);
+    }
+}
+
+impl Drop for UpdatePromptDlgUi {
+    fn drop(&mut self) {
+        let handler = self.default_handler.borrow();
+        if handler.is_some() {
+            nwg::unbind_event_handler(handler.as_ref().unwrap());
+        }
+    }
+}
+
+impl Deref for UpdatePromptDlgUi {
+    type Target = UpdatePromptDlg;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}